@@ -0,0 +1,33 @@
+// Build a pool from a config file instead of hardcoded builder calls.
+// Run with: cargo run --example config --features serde
+use mobc_surrealdb::SurrealPoolConfig; // Deserializable manager + pool settings
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Any serde format works (TOML, YAML, ...); JSON keeps the example dependency-free.
+    let config: SurrealPoolConfig = serde_json::from_str(
+        r#"{
+            "protocol": "ws",
+            "address": "127.0.0.1:8000",
+            "username": "root",
+            "password": "root",
+            "namespace": "accounts",
+            "database": "users",
+            "connect_timeout": "5s",
+            "max_open": 20,
+            "max_idle": 5,
+            "max_lifetime": "5m"
+        }"#,
+    )?;
+
+    // Validate the settings and build the ready-to-use pool.
+    let pool = config.build_pool()?;
+
+    // Get a connection from the pool; it already uses the configured namespace and database.
+    let conn = pool.get().await?;
+    let mut response = conn.query("RETURN 1").await?;
+    let one: Option<i32> = response.take(0)?;
+    println!("RETURN 1 -> {one:?}");
+
+    Ok(())
+}
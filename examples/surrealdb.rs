@@ -4,7 +4,6 @@ use mobc_surrealdb::SurrealDBConnectionManager; // The connection manager
 use serde::{Deserialize, Serialize}; // For serializing/deserializing our data types
 use std::time::Duration; // For configuring pool settings
 use surrealdb::sql::Thing; // SurrealDB's type for record IDs
-use tokio; // Tokio runtime for asynchronous execution
 
 // Define a struct to represent a person, with fields for ID, name, and age.
 // The ID is optional because it will be assigned by the database upon insertion.
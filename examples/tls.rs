@@ -0,0 +1,34 @@
+// Connect over wss to a server whose certificate is signed by a private CA.
+// Start a local server with a self-signed certificate, e.g.
+//   surreal start --user root --pass root --web-crt cert.pem --web-key key.pem
+// then run with: cargo run --example tls -- cert.pem
+use mobc::Pool;
+use mobc_surrealdb::{ConnectionProtocol, SurrealDBConnectionManager, TlsConfig};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let ca_path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "cert.pem".to_owned());
+
+    // Trust only the given CA (or self-signed certificate).
+    let tls = TlsConfig::new()
+        .root_certificate(std::fs::read(ca_path)?)
+        .public_roots(false);
+
+    let manager = SurrealDBConnectionManager::builder()
+        .protocol(ConnectionProtocol::Wss)
+        .address("localhost:8000")
+        .username("root")
+        .password("root")
+        .tls(tls)
+        .build()?;
+    let pool = Pool::builder().max_open(4).build(manager);
+
+    let conn = pool.get().await?;
+    let mut response = conn.query("RETURN 1").await?;
+    let one: Option<i32> = response.take(0)?;
+    println!("RETURN 1 over wss -> {one:?}");
+
+    Ok(())
+}
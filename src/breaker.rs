@@ -0,0 +1,243 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::ConfigError;
+
+/// Settings for a circuit breaker that stops `connect()` from piling up on a
+/// server that keeps failing.
+///
+/// After `failure_threshold` consecutive failed connects or health checks the
+/// circuit opens, and `connect()` fails at once with
+/// [`Error::CircuitOpen`](crate::Error::CircuitOpen). Once `open_duration`
+/// has passed the circuit half-opens: a single connect is let through as a
+/// probe, closing the circuit if it succeeds and opening it again if it
+/// fails.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+}
+
+impl CircuitBreaker {
+    /// Creates a breaker that opens after 5 consecutive failures and probes
+    /// the server again after 30s.
+    pub fn new() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets how many consecutive failures open the circuit.
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold;
+        self
+    }
+
+    /// Sets how long the circuit stays open before a probe is let through.
+    pub fn open_duration(mut self, open_duration: Duration) -> Self {
+        self.open_duration = open_duration;
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        if self.failure_threshold == 0 {
+            return Err(invalid("`failure_threshold` must be at least 1"));
+        }
+        if self.open_duration.is_zero() {
+            return Err(invalid("`open_duration` must be greater than zero"));
+        }
+        Ok(())
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The state of a manager's circuit breaker, as reported by
+/// [`SurrealDBConnectionManager::circuit_state`](crate::SurrealDBConnectionManager::circuit_state).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Connects go through; `failures` consecutive ones have failed so far.
+    Closed { failures: u32 },
+    /// Connects fail fast until the open duration has passed.
+    Open,
+    /// A probe connect is, or may be, in flight; others fail fast.
+    HalfOpen,
+}
+
+/// A circuit breaker's settings and its current state.
+#[derive(Debug)]
+pub(crate) struct Breaker {
+    settings: CircuitBreaker,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { since: Instant },
+    // A probe that never reports back (its connect was cancelled) is
+    // replaced once another open duration has passed.
+    HalfOpen { since: Instant },
+}
+
+impl Breaker {
+    pub(crate) fn new(settings: CircuitBreaker) -> Self {
+        Self {
+            settings,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        match *self.lock() {
+            State::Closed { failures } => CircuitState::Closed { failures },
+            State::Open { .. } => CircuitState::Open,
+            State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Returns whether a connect may go through; `false` means it should
+    /// fail fast.
+    pub(crate) fn try_acquire(&self) -> bool {
+        let mut state = self.lock();
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { since } | State::HalfOpen { since }
+                if since.elapsed() >= self.settings.open_duration =>
+            {
+                *state = State::HalfOpen {
+                    since: Instant::now(),
+                };
+                true
+            }
+            State::Open { .. } | State::HalfOpen { .. } => false,
+        }
+    }
+
+    /// Records the outcome of a connect or health check.
+    pub(crate) fn record(&self, succeeded: bool) {
+        let mut state = self.lock();
+        *state = match (&*state, succeeded) {
+            // Only a probe closes an open circuit.
+            (State::Open { since }, _) => State::Open { since: *since },
+            (_, true) => State::Closed { failures: 0 },
+            (State::Closed { failures }, false)
+                if failures + 1 < self.settings.failure_threshold =>
+            {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, false) => State::Open {
+                since: Instant::now(),
+            },
+        };
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // The state is always left consistent, so a poisoned lock is still usable.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn invalid(reason: &str) -> ConfigError {
+    ConfigError::InvalidCircuitBreaker(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+
+    const OPEN_DURATION: Duration = Duration::from_millis(50);
+
+    fn breaker() -> Breaker {
+        Breaker::new(
+            CircuitBreaker::new()
+                .failure_threshold(2)
+                .open_duration(OPEN_DURATION),
+        )
+    }
+
+    /// Returns a breaker that has just opened.
+    fn opened() -> Breaker {
+        let breaker = breaker();
+        breaker.record(false);
+        breaker.record(false);
+        breaker
+    }
+
+    #[test]
+    fn closed_counts_consecutive_failures() {
+        let breaker = breaker();
+        assert_eq!(breaker.state(), CircuitState::Closed { failures: 0 });
+        breaker.record(false);
+        assert_eq!(breaker.state(), CircuitState::Closed { failures: 1 });
+        breaker.record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed { failures: 0 });
+        assert!(breaker.try_acquire());
+    }
+
+    #[test]
+    fn opens_at_the_threshold_and_fails_fast() {
+        let breaker = opened();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.try_acquire());
+        // Stragglers finishing while the circuit is open change nothing.
+        breaker.record(true);
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn half_opens_for_a_single_probe_that_closes_it() {
+        let breaker = opened();
+        sleep(OPEN_DURATION);
+        assert!(breaker.try_acquire());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(!breaker.try_acquire());
+        breaker.record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed { failures: 0 });
+    }
+
+    #[test]
+    fn a_failed_probe_opens_it_again() {
+        let breaker = opened();
+        sleep(OPEN_DURATION);
+        assert!(breaker.try_acquire());
+        breaker.record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.try_acquire());
+    }
+
+    #[test]
+    fn a_lost_probe_is_replaced() {
+        let breaker = opened();
+        sleep(OPEN_DURATION);
+        assert!(breaker.try_acquire());
+        sleep(OPEN_DURATION);
+        assert!(breaker.try_acquire());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn validate_rejects_zero_settings() {
+        assert!(CircuitBreaker::new().validate().is_ok());
+        for circuit_breaker in [
+            CircuitBreaker::new().failure_threshold(0),
+            CircuitBreaker::new().open_duration(Duration::ZERO),
+        ] {
+            assert!(matches!(
+                circuit_breaker.validate(),
+                Err(ConfigError::InvalidCircuitBreaker(_))
+            ));
+        }
+    }
+}
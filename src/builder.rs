@@ -0,0 +1,590 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{OnceCell, Semaphore};
+use url::Url;
+
+use crate::breaker::{Breaker, CircuitBreaker};
+use crate::credentials::Credentials;
+use crate::error::ConfigError;
+use crate::protocol::protocol_from_scheme;
+use crate::provider::CredentialProvider;
+use crate::retry::RetryPolicy;
+use crate::secret::Secret;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+use crate::tls::TlsConfig;
+use crate::{ConnectionProtocol, HealthCheck, SelectionCheck, SurrealDBConnectionManager};
+
+/// A fluent builder for [`SurrealDBConnectionManager`].
+///
+/// Every setting has a named setter, so credentials and namespace/database
+/// cannot be swapped by accident. [`build`](Self::build) validates the
+/// combination and reports what is wrong.
+#[derive(Debug, Clone, Default)]
+pub struct SurrealDBConnectionManagerBuilder {
+    protocol: ConnectionProtocol,
+    address: Option<String>,
+    username: Option<String>,
+    password: Option<Secret>,
+    credentials: Option<Credentials>,
+    credential_provider: Option<Arc<dyn CredentialProvider>>,
+    namespace: Option<String>,
+    database: Option<String>,
+    connect_timeout: Option<Duration>,
+    check_timeout: Option<Duration>,
+    dial_timeout: Option<Duration>,
+    signin_timeout: Option<Duration>,
+    select_timeout: Option<Duration>,
+    health_check_timeout: Option<Duration>,
+    health_check: HealthCheck,
+    selection_check: SelectionCheck,
+    session_refresh: Option<Duration>,
+    reset_session: bool,
+    reset_variables: Vec<String>,
+    retry: Option<RetryPolicy>,
+    circuit_breaker: Option<CircuitBreaker>,
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    tls: Option<TlsConfig>,
+}
+
+impl SurrealDBConnectionManagerBuilder {
+    /// Creates a builder with the default protocol (ws) and no settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the connection protocol. Defaults to
+    /// [`ConnectionProtocol::default`]: `ws`, or the first engine compiled in.
+    /// A build with only on-disk engines has to set one with its path.
+    pub fn protocol(mut self, protocol: ConnectionProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the server address (host:port/path), without a scheme. Embedded
+    /// engines have no server, and [`build`](Self::build) rejects an address
+    /// for them.
+    ///
+    /// [`build`](Self::build) checks that it is a valid `host[:port][/path]`.
+    /// A scheme matching the protocol (`ws://host:8000` with `Ws`) is
+    /// stripped; any other scheme is rejected.
+    pub fn address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    /// Sets the username of the root user to sign in as.
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Sets the password of the root user to sign in as.
+    pub fn password(mut self, password: impl Into<Secret>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Sets the identity to sign in with, e.g. a namespace, database or
+    /// record user. Cannot be combined with [`username`](Self::username) and
+    /// [`password`](Self::password).
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Asks `provider` for credentials every time a connection is opened, so
+    /// rotated credentials are picked up without rebuilding the pool. Cannot
+    /// be combined with any other credential setting.
+    pub fn credential_provider(mut self, provider: impl CredentialProvider) -> Self {
+        self.credential_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Authenticates every new connection with a pre-issued token instead
+    /// of signing in. Shorthand for `credentials(Credentials::token(..))`.
+    pub fn token(self, token: impl Into<Secret>) -> Self {
+        self.credentials(Credentials::token(token))
+    }
+
+    /// Sets the namespace selected on every new connection.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Sets the database selected on every new connection. Requires a namespace.
+    pub fn database(mut self, database: impl Into<String>) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    /// Bounds how long establishing a connection (dial, signin and
+    /// namespace/database selection) may take. Unbounded by default.
+    pub fn connect_timeout(mut self, connect_timeout: Option<Duration>) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Bounds how long `check()` may take as a whole: the health check plus
+    /// any session refresh, re-signin or reset. Unbounded by default.
+    pub fn check_timeout(mut self, check_timeout: Option<Duration>) -> Self {
+        self.check_timeout = check_timeout;
+        self
+    }
+
+    /// Bounds how long opening the connection to the server may take, e.g.
+    /// when the host does not answer. Unbounded by default.
+    pub fn dial_timeout(mut self, dial_timeout: Option<Duration>) -> Self {
+        self.dial_timeout = dial_timeout;
+        self
+    }
+
+    /// Bounds how long a single signin may take, on connect and when a
+    /// session is signed in again. Unbounded by default.
+    pub fn signin_timeout(mut self, signin_timeout: Option<Duration>) -> Self {
+        self.signin_timeout = signin_timeout;
+        self
+    }
+
+    /// Bounds how long selecting the namespace and database may take,
+    /// including the [`SelectionCheck`]. Unbounded by default.
+    pub fn select_timeout(mut self, select_timeout: Option<Duration>) -> Self {
+        self.select_timeout = select_timeout;
+        self
+    }
+
+    /// Bounds each query `check()` runs to test a connection on its own: the
+    /// [`HealthCheck`] probe and, with a
+    /// [`session_refresh`](Self::session_refresh) margin, the lookup of the
+    /// session's expiry. Signing in again falls under `signin_timeout`
+    /// instead. Unbounded by default.
+    pub fn health_check_timeout(mut self, health_check_timeout: Option<Duration>) -> Self {
+        self.health_check_timeout = health_check_timeout;
+        self
+    }
+
+    /// Sets the policy used to check connections on checkout.
+    pub fn health_check(mut self, health_check: HealthCheck) -> Self {
+        self.health_check = health_check;
+        self
+    }
+
+    /// Sets whether new connections verify, or create, the configured
+    /// namespace and database. Defaults to [`SelectionCheck::Skip`].
+    pub fn selection_check(mut self, selection_check: SelectionCheck) -> Self {
+        self.selection_check = selection_check;
+        self
+    }
+
+    /// Signs a pooled connection in again when its session (`$session.exp`)
+    /// expires within `margin`, checked on every health check. Sessions that
+    /// already failed with an authentication error are signed in again
+    /// regardless. Disabled by default.
+    pub fn session_refresh(mut self, margin: Option<Duration>) -> Self {
+        self.session_refresh = margin;
+        self
+    }
+
+    /// Restores every pooled connection to its configured session before it
+    /// is handed out again: the namespace and database are reselected, the
+    /// [`reset_variables`](Self::reset_variables) are unset, and whatever
+    /// identity a borrower signed in as is replaced by the configured one.
+    ///
+    /// The reset runs where mobc calls `check()` (on every checkout with
+    /// mobc's defaults, less often with `health_check_interval` or
+    /// `test_on_check_out(false)`), followed by the health check. It signs in
+    /// each time, so prefer token credentials when checkouts are frequent.
+    /// Disabled by default.
+    pub fn reset_session(mut self, enabled: bool) -> Self {
+        self.reset_session = enabled;
+        self
+    }
+
+    /// Names the session variables (set with `conn.set(..)`) that a session
+    /// reset unsets. SurrealDB cannot list a session's variables, so they have
+    /// to be named here.
+    pub fn reset_variables<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.reset_variables = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Retries dialing and signing in on `connect()` after a transient
+    /// failure, as set out by `policy`. Failures are not retried by default.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Guards `connect()` with a circuit breaker, so that once the server
+    /// keeps failing connects fail fast instead of piling up on it. Disabled
+    /// by default.
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Sets the TLS settings (trusted CAs, client certificate, TLS library)
+    /// for `wss` and `https` connections. Without it SurrealDB's defaults
+    /// apply.
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Validates the settings and builds the manager.
+    pub fn build(self) -> Result<SurrealDBConnectionManager, ConfigError> {
+        // Embedded engines have no server address and accept anonymous
+        // connections; remote servers need both.
+        let embedded = self.protocol.is_embedded();
+        if let Some(path) = self.protocol.datastore_path() {
+            if path.as_os_str().is_empty() {
+                return Err(ConfigError::MissingField("datastore path"));
+            }
+        }
+        let address = match embedded {
+            true if self.address.is_some() => {
+                return Err(ConfigError::Embedded {
+                    setting: "address",
+                    reason: "the datastore is opened in-process, not dialed",
+                })
+            }
+            true => String::new(),
+            false => server_address(&self.protocol, required("address", self.address)?)?,
+        };
+        let root = self.username.is_some() || self.password.is_some();
+        match (&self.credential_provider, &self.credentials) {
+            (Some(_), Some(_)) => {
+                return Err(ConfigError::ConflictingSettings(
+                    "credential_provider",
+                    "credentials",
+                ))
+            }
+            (Some(_), None) if root => {
+                return Err(ConfigError::ConflictingSettings(
+                    "credential_provider",
+                    "username and password",
+                ))
+            }
+            (None, Some(_)) if root => {
+                return Err(ConfigError::ConflictingSettings(
+                    "credentials",
+                    "username and password",
+                ))
+            }
+            _ => {}
+        }
+        let credentials = match (self.credential_provider, self.credentials) {
+            (Some(provider), _) => Some(provider),
+            (None, Some(credentials)) => {
+                credentials.validate()?;
+                Some(Arc::new(credentials) as Arc<dyn CredentialProvider>)
+            }
+            (None, None) if embedded && !root => None,
+            (None, None) => {
+                let credentials = Credentials::root(
+                    required("username", self.username)?,
+                    self.password.ok_or(ConfigError::MissingField("password"))?,
+                );
+                credentials.validate()?;
+                Some(Arc::new(credentials) as Arc<dyn CredentialProvider>)
+            }
+        };
+
+        if credentials.is_none() && matches!(self.health_check, HealthCheck::Authenticated) {
+            return Err(ConfigError::RequiresCredentials(
+                "HealthCheck::Authenticated",
+            ));
+        }
+
+        let namespace = optional("namespace", self.namespace)?;
+        let database = optional("database", self.database)?;
+        if database.is_some() && namespace.is_none() {
+            return Err(ConfigError::DatabaseWithoutNamespace);
+        }
+
+        non_zero("connect_timeout", self.connect_timeout)?;
+        non_zero("check_timeout", self.check_timeout)?;
+        non_zero("dial_timeout", self.dial_timeout)?;
+        non_zero("signin_timeout", self.signin_timeout)?;
+        non_zero("select_timeout", self.select_timeout)?;
+        non_zero("health_check_timeout", self.health_check_timeout)?;
+        if let Some(retry) = &self.retry {
+            retry.validate()?;
+        }
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.validate()?;
+        }
+
+        #[cfg(any(feature = "rustls", feature = "native-tls"))]
+        let tls = match &self.tls {
+            Some(_) if !self.protocol.is_secure() => {
+                return Err(ConfigError::InvalidTls(format!(
+                    "protocol `{}` does not use TLS",
+                    self.protocol
+                )))
+            }
+            Some(tls) => Some(tls.surreal_config()?),
+            None => None,
+        };
+
+        Ok(SurrealDBConnectionManager {
+            protocol: self.protocol,
+            db_url: address,
+            credentials,
+            db_namespace: namespace,
+            db_database: database,
+            connect_timeout: self.connect_timeout,
+            check_timeout: self.check_timeout,
+            dial_timeout: self.dial_timeout,
+            signin_timeout: self.signin_timeout,
+            select_timeout: self.select_timeout,
+            health_check_timeout: self.health_check_timeout,
+            health_check: self.health_check,
+            selection_check: self.selection_check,
+            session_refresh: self.session_refresh,
+            reset_session: self.reset_session,
+            reset_variables: self.reset_variables,
+            retry: self.retry,
+            breaker: self.circuit_breaker.map(Breaker::new),
+            embedded: OnceCell::new(),
+            embedded_lease: Arc::new(Semaphore::new(1)),
+            next_id: AtomicU64::new(0),
+            #[cfg(any(feature = "rustls", feature = "native-tls"))]
+            tls,
+        })
+    }
+}
+
+fn required(field: &'static str, value: Option<String>) -> Result<String, ConfigError> {
+    optional(field, value)?.ok_or(ConfigError::MissingField(field))
+}
+
+fn optional(field: &'static str, value: Option<String>) -> Result<Option<String>, ConfigError> {
+    match value {
+        Some(value) if value.trim().is_empty() => Err(ConfigError::EmptyField(field)),
+        value => Ok(value),
+    }
+}
+
+/// Validates a server address, stripping a scheme that matches `protocol`.
+fn server_address(protocol: &ConnectionProtocol, address: String) -> Result<String, ConfigError> {
+    let invalid = |reason: &str| ConfigError::InvalidAddress {
+        address: address.clone(),
+        reason: reason.to_owned(),
+    };
+
+    let bare = match address.split_once("://") {
+        Some((scheme, rest)) => match protocol_from_scheme(scheme) {
+            Ok(from_address) if from_address == *protocol => rest,
+            _ => {
+                return Err(ConfigError::SchemeMismatch {
+                    address,
+                    protocol: protocol.to_string(),
+                })
+            }
+        },
+        None => address.as_str(),
+    };
+    if bare.chars().any(char::is_whitespace) {
+        return Err(invalid("contains whitespace"));
+    }
+
+    let url = Url::parse(&protocol.endpoint(bare)).map_err(|err| invalid(&err.to_string()))?;
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err(invalid(
+            "credentials belong in the username and password settings",
+        ));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(invalid("query strings and fragments are not supported"));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("missing host"));
+    }
+    Ok(bare.to_owned())
+}
+
+pub(crate) fn non_zero(field: &'static str, timeout: Option<Duration>) -> Result<(), ConfigError> {
+    match timeout {
+        Some(timeout) if timeout.is_zero() => Err(ConfigError::ZeroTimeout(field)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(all(test, feature = "ws"))]
+mod tests {
+    use super::*;
+
+    fn remote() -> SurrealDBConnectionManagerBuilder {
+        SurrealDBConnectionManager::builder()
+            .address("127.0.0.1:8000")
+            .username("root")
+            .password("root")
+    }
+
+    #[test]
+    fn build_accepts_a_complete_remote_config() {
+        let manager = remote().namespace("app").database("app").build().unwrap();
+        assert_eq!(manager.db_url, "127.0.0.1:8000");
+        assert_eq!(manager.db_namespace.as_deref(), Some("app"));
+        assert_eq!(manager.db_database.as_deref(), Some("app"));
+    }
+
+    #[test]
+    fn build_requires_an_address_and_root_credentials() {
+        let err = SurrealDBConnectionManager::builder()
+            .username("root")
+            .password("root")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, ConfigError::MissingField("address"));
+
+        let builder = SurrealDBConnectionManager::builder().address("127.0.0.1:8000");
+        let err = builder.clone().password("root").build().unwrap_err();
+        assert_eq!(err, ConfigError::MissingField("username"));
+        let err = builder.username("root").build().unwrap_err();
+        assert_eq!(err, ConfigError::MissingField("password"));
+    }
+
+    #[test]
+    fn build_rejects_empty_fields() {
+        let err = remote().address(" ").build().unwrap_err();
+        assert_eq!(err, ConfigError::EmptyField("address"));
+        let err = remote().username("").build().unwrap_err();
+        assert_eq!(err, ConfigError::EmptyField("username"));
+        let err = remote().namespace("").build().unwrap_err();
+        assert_eq!(err, ConfigError::EmptyField("namespace"));
+    }
+
+    #[test]
+    fn build_rejects_a_database_without_a_namespace() {
+        let err = remote().database("app").build().unwrap_err();
+        assert_eq!(err, ConfigError::DatabaseWithoutNamespace);
+    }
+
+    #[test]
+    fn build_rejects_zero_timeouts() {
+        let err = remote()
+            .connect_timeout(Some(Duration::ZERO))
+            .build()
+            .unwrap_err();
+        assert_eq!(err, ConfigError::ZeroTimeout("connect_timeout"));
+        let err = remote()
+            .health_check_timeout(Some(Duration::ZERO))
+            .build()
+            .unwrap_err();
+        assert_eq!(err, ConfigError::ZeroTimeout("health_check_timeout"));
+    }
+
+    #[cfg(feature = "kv-mem")]
+    #[test]
+    fn build_requires_credentials_for_the_authenticated_health_check() {
+        let err = SurrealDBConnectionManager::builder()
+            .protocol(ConnectionProtocol::Mem)
+            .health_check(HealthCheck::Authenticated)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::RequiresCredentials("HealthCheck::Authenticated")
+        );
+    }
+
+    #[test]
+    fn build_rejects_conflicting_credentials() {
+        let err = remote().token("token").build().unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::ConflictingSettings("credentials", "username and password")
+        );
+
+        let err = remote()
+            .credential_provider(Credentials::token("token"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::ConflictingSettings("credential_provider", "username and password")
+        );
+
+        let err = SurrealDBConnectionManager::builder()
+            .address("127.0.0.1:8000")
+            .token("token")
+            .credential_provider(Credentials::token("token"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::ConflictingSettings("credential_provider", "credentials")
+        );
+    }
+
+    #[cfg(feature = "kv-mem")]
+    #[test]
+    fn build_rejects_an_address_for_embedded_engines() {
+        let err = SurrealDBConnectionManager::builder()
+            .protocol(ConnectionProtocol::Mem)
+            .address("127.0.0.1:8000")
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::Embedded {
+                setting: "address",
+                ..
+            }
+        ));
+    }
+
+    fn invalid_reason(address: &str) -> String {
+        match server_address(&ConnectionProtocol::Ws, address.to_owned()) {
+            Err(ConfigError::InvalidAddress { reason, .. }) => reason,
+            other => panic!("expected an invalid address, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn server_address_strips_a_matching_scheme() {
+        let address = server_address(&ConnectionProtocol::Ws, "ws://127.0.0.1:8000".to_owned());
+        assert_eq!(address.unwrap(), "127.0.0.1:8000");
+        let address = server_address(&ConnectionProtocol::Ws, "db.internal/rpc".to_owned());
+        assert_eq!(address.unwrap(), "db.internal/rpc");
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn server_address_rejects_another_scheme() {
+        let err = server_address(&ConnectionProtocol::Http, "ws://127.0.0.1:8000".to_owned());
+        assert_eq!(
+            err.unwrap_err(),
+            ConfigError::SchemeMismatch {
+                address: "ws://127.0.0.1:8000".to_owned(),
+                protocol: "http".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn server_address_rejects_malformed_addresses() {
+        assert_eq!(
+            invalid_reason("root:root@127.0.0.1:8000"),
+            "credentials belong in the username and password settings"
+        );
+        assert_eq!(
+            invalid_reason("127.0.0.1:8000?ns=app"),
+            "query strings and fragments are not supported"
+        );
+        // The url crate already refuses these, with its own wording.
+        invalid_reason(":8000");
+        invalid_reason("");
+        assert_eq!(invalid_reason("127.0.0.1 :8000"), "contains whitespace");
+        assert_eq!(invalid_reason(" 127.0.0.1:8000"), "contains whitespace");
+    }
+}
@@ -0,0 +1,517 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use mobc::Pool;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer};
+
+use crate::breaker::CircuitBreaker;
+use crate::builder::non_zero;
+use crate::credentials::Credentials;
+#[cfg(feature = "serde")]
+use crate::dsn::{parse_duration, parse_health_check, parse_selection_check};
+use crate::error::ConfigError;
+use crate::retry::RetryPolicy;
+use crate::secret::Secret;
+use crate::{ConnectionProtocol, HealthCheck, SelectionCheck, SurrealDBConnectionManager};
+
+/// Manager and pool settings in one struct, so a config file (TOML, YAML,
+/// JSON, ..., with the `serde` feature) or a connection string (see
+/// [`from_url`](Self::from_url)) can be the single source of truth for a
+/// pool.
+///
+/// Durations accept either a number of seconds or a string such as `"500ms"`,
+/// `"5s"`, `"2m"` or `"1h"`. Pool settings left out keep mobc's defaults.
+///
+/// ```toml
+/// protocol = "wss"
+/// address = "db.internal:8000"
+/// username = "root"
+/// password = "root"
+/// namespace = "accounts"
+/// database = "users"
+/// connect_timeout = "5s"
+/// max_open = 20
+/// max_idle = 5
+/// max_lifetime = "5m"
+/// ```
+///
+/// A user other than root, or a pre-issued token, goes in a `credentials`
+/// section instead of `username` and `password`:
+///
+/// ```toml
+/// [credentials]
+/// kind = "database"
+/// namespace = "accounts"
+/// database = "users"
+/// username = "api"
+/// password = "secret"
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(deny_unknown_fields))]
+pub struct SurrealPoolConfig {
+    /// Connection protocol (`ws`, `wss`, `http`, `https` or `mem`), or an
+    /// on-disk engine with its datastore path (`rocksdb:///var/lib/surreal`).
+    /// Defaults to `ws`, or the first engine compiled in; a build with only
+    /// on-disk engines has to set it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub protocol: ConnectionProtocol,
+    /// Server address (host:port/path), without a scheme. Must be unset for
+    /// embedded engines.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub address: Option<String>,
+    /// Username of the root user to sign in as. Optional for embedded engines.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub username: Option<String>,
+    /// Password of the root user to sign in as. Optional for embedded engines.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub password: Option<Secret>,
+    /// A user other than root to sign in as, or a token to authenticate
+    /// with. Conflicts with `username` and `password`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub credentials: Option<CredentialsConfig>,
+    /// Namespace selected on every new connection.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub namespace: Option<String>,
+    /// Database selected on every new connection. Requires a namespace.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub database: Option<String>,
+    /// Bound on establishing a connection.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub connect_timeout: Option<Duration>,
+    /// Bound on `check()` as a whole, including any re-signin or reset.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub check_timeout: Option<Duration>,
+    /// Bound on opening the connection to the server.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub dial_timeout: Option<Duration>,
+    /// Bound on a single signin.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub signin_timeout: Option<Duration>,
+    /// Bound on selecting the namespace and database.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub select_timeout: Option<Duration>,
+    /// Bound on each health check query: the probe and the session expiry
+    /// lookup.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub health_check_timeout: Option<Duration>,
+    /// Re-sign in a pooled connection when its session expires this soon.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub session_refresh: Option<Duration>,
+    /// Health check policy (`query`, `health`, `version`, `authenticated` or
+    /// `disabled`). Defaults to `query`.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "health_check"))]
+    pub health_check: HealthCheck,
+    /// Whether new connections verify or create the namespace and database
+    /// (`skip`, `verify` or `create`). Defaults to `skip`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "selection_check")
+    )]
+    pub selection_check: SelectionCheck,
+    /// Restore each connection's namespace, database and identity before it
+    /// is handed out again. Defaults to `false`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reset_session: bool,
+    /// Session variables unset by a session reset.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reset_variables: Vec<String>,
+    /// Attempts `connect()` makes at dialing and signing in. Setting any
+    /// `retry_*` field turns on retries, with [`RetryPolicy`] defaults for
+    /// the rest.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub retry_attempts: Option<u32>,
+    /// Backoff before the first retry.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub retry_backoff: Option<Duration>,
+    /// Cap on the backoff between two retries.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub retry_max_backoff: Option<Duration>,
+    /// Give up retrying once this much time has passed since the first attempt.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub retry_max_elapsed: Option<Duration>,
+    /// Consecutive failures that open the circuit breaker. Setting either
+    /// `circuit_*` field turns the breaker on, with [`CircuitBreaker`]
+    /// defaults for the other.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub circuit_failure_threshold: Option<u32>,
+    /// How long the circuit stays open before a probe connect.
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub circuit_open_duration: Option<Duration>,
+
+    /// Maximum number of open connections (mobc `max_open`). Embedded
+    /// engines hold a single connection, so for them it defaults to, and
+    /// must be, 1.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_open: Option<u64>,
+    /// Maximum number of idle connections (mobc `max_idle`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_idle: Option<u64>,
+    /// Maximum lifetime of a connection (mobc `max_lifetime`).
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub max_lifetime: Option<Duration>,
+    /// Maximum time a connection may sit idle (mobc `max_idle_lifetime`).
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub max_idle_lifetime: Option<Duration>,
+    /// How long `pool.get()` waits for a connection (mobc `get_timeout`).
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub get_timeout: Option<Duration>,
+    /// Interval between health checks of idle connections (mobc `health_check_interval`).
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "duration"))]
+    pub health_check_interval: Option<Duration>,
+    /// Whether connections are checked on checkout (mobc `test_on_check_out`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub test_on_check_out: Option<bool>,
+}
+
+impl SurrealPoolConfig {
+    /// Validates the manager settings and builds the manager.
+    pub fn manager(&self) -> Result<SurrealDBConnectionManager, ConfigError> {
+        let mut builder = SurrealDBConnectionManager::builder()
+            .protocol(self.protocol.clone())
+            .connect_timeout(self.connect_timeout)
+            .check_timeout(self.check_timeout)
+            .dial_timeout(self.dial_timeout)
+            .signin_timeout(self.signin_timeout)
+            .select_timeout(self.select_timeout)
+            .health_check_timeout(self.health_check_timeout)
+            .session_refresh(self.session_refresh)
+            .health_check(self.health_check.clone())
+            .selection_check(self.selection_check)
+            .reset_session(self.reset_session)
+            .reset_variables(self.reset_variables.iter().cloned());
+        if let Some(retry) = self.retry() {
+            builder = builder.retry(retry);
+        }
+        if let Some(circuit_breaker) = self.circuit_breaker() {
+            builder = builder.circuit_breaker(circuit_breaker);
+        }
+        if let Some(address) = &self.address {
+            builder = builder.address(address);
+        }
+        if let Some(credentials) = &self.credentials {
+            builder = builder.credentials(credentials.credentials());
+        }
+        if let Some(username) = &self.username {
+            builder = builder.username(username);
+        }
+        if let Some(password) = &self.password {
+            builder = builder.password(password.clone());
+        }
+        if let Some(namespace) = &self.namespace {
+            builder = builder.namespace(namespace);
+        }
+        if let Some(database) = &self.database {
+            builder = builder.database(database);
+        }
+        builder.build()
+    }
+
+    /// The retry policy described by the `retry_*` fields, if any is set.
+    fn retry(&self) -> Option<RetryPolicy> {
+        if self.retry_attempts.is_none()
+            && self.retry_backoff.is_none()
+            && self.retry_max_backoff.is_none()
+            && self.retry_max_elapsed.is_none()
+        {
+            return None;
+        }
+        let mut retry = RetryPolicy::new().max_elapsed(self.retry_max_elapsed);
+        if let Some(attempts) = self.retry_attempts {
+            retry = retry.max_attempts(attempts);
+        }
+        if let Some(backoff) = self.retry_backoff {
+            retry = retry.initial_backoff(backoff);
+        }
+        if let Some(max_backoff) = self.retry_max_backoff {
+            retry = retry.max_backoff(max_backoff);
+        }
+        Some(retry)
+    }
+
+    /// The circuit breaker described by the `circuit_*` fields, if any is set.
+    fn circuit_breaker(&self) -> Option<CircuitBreaker> {
+        if self.circuit_failure_threshold.is_none() && self.circuit_open_duration.is_none() {
+            return None;
+        }
+        let mut circuit_breaker = CircuitBreaker::new();
+        if let Some(threshold) = self.circuit_failure_threshold {
+            circuit_breaker = circuit_breaker.failure_threshold(threshold);
+        }
+        if let Some(open_duration) = self.circuit_open_duration {
+            circuit_breaker = circuit_breaker.open_duration(open_duration);
+        }
+        Some(circuit_breaker)
+    }
+
+    /// Builds the manager and a pool configured with the mobc settings.
+    pub fn build_pool(&self) -> Result<Pool<SurrealDBConnectionManager>, ConfigError> {
+        let manager = self.manager()?;
+
+        // mobc panics on these being zero, so reject them here instead.
+        non_zero("max_lifetime", self.max_lifetime)?;
+        non_zero("max_idle_lifetime", self.max_idle_lifetime)?;
+        non_zero("get_timeout", self.get_timeout)?;
+        non_zero("health_check_interval", self.health_check_interval)?;
+
+        let mut builder = Pool::builder();
+        // An embedded datastore has one session, so it backs one connection.
+        let max_open = match (self.protocol.is_embedded(), self.max_open) {
+            (true, Some(max_open)) if max_open != 1 => {
+                return Err(ConfigError::Embedded {
+                    setting: "max_open",
+                    reason: "the datastore has a single session, so the pool holds one connection",
+                })
+            }
+            (true, _) => Some(1),
+            (false, max_open) => max_open,
+        };
+        if let Some(max_open) = max_open {
+            builder = builder.max_open(max_open);
+        }
+        if let Some(max_idle) = self.max_idle {
+            builder = builder.max_idle(max_idle);
+        }
+        if self.max_lifetime.is_some() {
+            builder = builder.max_lifetime(self.max_lifetime);
+        }
+        if self.max_idle_lifetime.is_some() {
+            builder = builder.max_idle_lifetime(self.max_idle_lifetime);
+        }
+        if self.get_timeout.is_some() {
+            builder = builder.get_timeout(self.get_timeout);
+        }
+        if self.health_check_interval.is_some() {
+            builder = builder.health_check_interval(self.health_check_interval);
+        }
+        if let Some(test_on_check_out) = self.test_on_check_out {
+            builder = builder.test_on_check_out(test_on_check_out);
+        }
+        Ok(builder.build(manager))
+    }
+}
+
+/// The identity a [`SurrealPoolConfig`] signs in with, as its `credentials`
+/// section; `kind` selects the variant. See [`Credentials`] for what each one
+/// does.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize),
+    serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)
+)]
+pub enum CredentialsConfig {
+    /// A root user.
+    Root { username: String, password: Secret },
+    /// A user defined on a namespace.
+    Namespace {
+        namespace: String,
+        username: String,
+        password: Secret,
+    },
+    /// A user defined on a database.
+    Database {
+        namespace: String,
+        database: String,
+        username: String,
+        password: Secret,
+    },
+    /// A record user, signed in through an access method. Params are passed
+    /// as strings.
+    Record {
+        namespace: String,
+        database: String,
+        access: String,
+        #[cfg_attr(feature = "serde", serde(default))]
+        params: BTreeMap<String, Secret>,
+    },
+    /// A pre-issued token, used to `authenticate` instead of signing in.
+    Token { token: Secret },
+}
+
+impl CredentialsConfig {
+    fn credentials(&self) -> Credentials {
+        match self.clone() {
+            CredentialsConfig::Root { username, password } => Credentials::root(username, password),
+            CredentialsConfig::Namespace {
+                namespace,
+                username,
+                password,
+            } => Credentials::namespace(namespace, username, password),
+            CredentialsConfig::Database {
+                namespace,
+                database,
+                username,
+                password,
+            } => Credentials::database(namespace, database, username, password),
+            CredentialsConfig::Record {
+                namespace,
+                database,
+                access,
+                params,
+            } => Credentials::record(
+                namespace,
+                database,
+                access,
+                params
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.expose())),
+            ),
+            CredentialsConfig::Token { token } => Credentials::token(token),
+        }
+    }
+}
+
+/// A duration written either as seconds or as a string with a unit.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDuration {
+    Seconds(u64),
+    Text(String),
+}
+
+#[cfg(feature = "serde")]
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    match Option::<RawDuration>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(RawDuration::Seconds(secs)) => Ok(Some(Duration::from_secs(secs))),
+        Some(RawDuration::Text(text)) => parse_duration(&text)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid duration `{text}`"))),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn health_check<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HealthCheck, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    parse_health_check(&text)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid health check `{text}`")))
+}
+
+#[cfg(feature = "serde")]
+fn selection_check<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SelectionCheck, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    parse_selection_check(&text)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid selection check `{text}`")))
+}
+
+#[cfg(all(test, any(feature = "serde", feature = "ws", feature = "kv-mem")))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_durations_as_text_or_seconds() {
+        let config: SurrealPoolConfig = serde_json::from_value(serde_json::json!({
+            "address": "127.0.0.1:8000",
+            "connect_timeout": "5m",
+            "get_timeout": 30,
+            "health_check": "version",
+        }))
+        .unwrap();
+        assert_eq!(config.address.as_deref(), Some("127.0.0.1:8000"));
+        assert_eq!(config.connect_timeout, Some(Duration::from_secs(300)));
+        assert_eq!(config.get_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(config.check_timeout, None);
+        assert!(matches!(config.health_check, HealthCheck::Version));
+
+        let err = serde_json::from_value::<SurrealPoolConfig>(serde_json::json!({
+            "connect_timeout": "soon",
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid duration `soon`"), "{err}");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn rejects_unknown_fields() {
+        let err = serde_json::from_value::<SurrealPoolConfig>(serde_json::json!({
+            "adress": "127.0.0.1:8000",
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `adress`"), "{err}");
+
+        let err = serde_json::from_value::<SurrealPoolConfig>(serde_json::json!({
+            "credentials": { "kind": "token", "token": "t", "ttl": 60 },
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `ttl`"), "{err}");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_the_credentials_section_by_kind() {
+        let config: SurrealPoolConfig = serde_json::from_value(serde_json::json!({
+            "credentials": {
+                "kind": "record",
+                "namespace": "app",
+                "database": "app",
+                "access": "user",
+                "params": { "email": "info@example.com" },
+            },
+        }))
+        .unwrap();
+        match config.credentials {
+            Some(CredentialsConfig::Record { access, params, .. }) => {
+                assert_eq!(access, "user");
+                assert_eq!(params["email"].expose(), "info@example.com");
+            }
+            other => panic!("expected record credentials, got {other:?}"),
+        }
+
+        let config: SurrealPoolConfig = serde_json::from_value(serde_json::json!({
+            "credentials": { "kind": "token", "token": "t" },
+        }))
+        .unwrap();
+        assert!(matches!(
+            config.credentials,
+            Some(CredentialsConfig::Token { .. })
+        ));
+
+        let err = serde_json::from_value::<SurrealPoolConfig>(serde_json::json!({
+            "credentials": { "kind": "anonymous" },
+        }))
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("unknown variant `anonymous`"),
+            "{err}"
+        );
+    }
+
+    #[cfg(feature = "ws")]
+    #[test]
+    fn build_pool_rejects_zero_timeouts() {
+        let config = SurrealPoolConfig {
+            protocol: ConnectionProtocol::Ws,
+            address: Some("127.0.0.1:8000".to_owned()),
+            username: Some("root".to_owned()),
+            password: Some("root".into()),
+            get_timeout: Some(Duration::ZERO),
+            ..SurrealPoolConfig::default()
+        };
+        let err = config.build_pool().err().unwrap();
+        assert_eq!(err, ConfigError::ZeroTimeout("get_timeout"));
+    }
+
+    #[cfg(feature = "kv-mem")]
+    #[tokio::test]
+    async fn build_pool_holds_one_embedded_connection() {
+        let mut config = SurrealPoolConfig {
+            protocol: ConnectionProtocol::Mem,
+            ..SurrealPoolConfig::default()
+        };
+        let pool = config.build_pool().unwrap();
+        assert_eq!(pool.state().await.max_open, 1);
+
+        config.max_open = Some(4);
+        let err = config.build_pool().err().unwrap();
+        assert!(matches!(
+            err,
+            ConfigError::Embedded {
+                setting: "max_open",
+                ..
+            }
+        ));
+    }
+}
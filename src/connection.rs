@@ -0,0 +1,241 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use surrealdb::engine::any::Any;
+use surrealdb::error::Api;
+use surrealdb::method::{
+    Authenticate, Create, Delete, Export, Health, Import, Insert, IntoFn, Invalidate, Query, Run,
+    Select, Signin, Signup, Unset, Update, Upsert, UseDb, UseNs, Version,
+};
+use surrealdb::opt::auth::{self, Credentials, Jwt};
+use surrealdb::opt::{CreateResource, IntoExportDestination, IntoQuery, IntoResource, WaitFor};
+use surrealdb::Surreal;
+use tokio::sync::OwnedSemaphorePermit;
+
+use crate::session;
+
+/// A pooled SurrealDB client, as handed out by the pool.
+///
+/// It forwards the methods of [`Surreal<Any>`], so queries are run on it
+/// directly, and it carries metadata about the pooled connection. It is not
+/// `Clone`, and it does not hand out the `Surreal` it wraps: the pool owns
+/// the connection, and a borrower only sees it for the duration of a
+/// checkout.
+///
+/// A connection flagged with [`mark_broken`](Self::mark_broken), or by
+/// [`observe`](Self::observe) after a transport error, is discarded when it
+/// is returned to the pool, without a round trip to the server.
+pub struct PooledSurreal {
+    client: Surreal<Any>,
+    id: u64,
+    created_at: Instant,
+    use_count: u64,
+    last_checked: Option<Instant>,
+    broken: AtomicBool,
+    // Held by an embedded engine's one open connection until it is dropped.
+    _lease: Option<OwnedSemaphorePermit>,
+}
+
+impl PooledSurreal {
+    pub(crate) fn new(id: u64, client: Surreal<Any>, lease: Option<OwnedSemaphorePermit>) -> Self {
+        Self {
+            client,
+            id,
+            created_at: Instant::now(),
+            use_count: 0,
+            last_checked: None,
+            broken: AtomicBool::new(false),
+            _lease: lease,
+        }
+    }
+
+    /// Identifies the connection among those opened by its manager.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// When the connection was opened.
+    pub fn created_at(&self) -> Instant {
+        self.created_at
+    }
+
+    /// How many checkouts of this connection have been returned to the pool.
+    pub fn use_count(&self) -> u64 {
+        self.use_count
+    }
+
+    /// When the connection last passed `check()`, if ever.
+    pub fn last_checked(&self) -> Option<Instant> {
+        self.last_checked
+    }
+
+    /// Flags the connection as unusable, so the pool discards it on return
+    /// instead of handing it out again.
+    pub fn mark_broken(&self) {
+        self.broken.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the connection was flagged as unusable.
+    pub fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::Relaxed)
+    }
+
+    /// Passes `result` through, flagging the connection as broken if it
+    /// failed because the connection to the server was lost, e.g.
+    /// `conn.observe(conn.query("SELECT * FROM user").await)?`.
+    // The error type is the client's own; boxing it would only get in the way.
+    #[allow(clippy::result_large_err)]
+    pub fn observe<T>(&self, result: Result<T, surrealdb::Error>) -> Result<T, surrealdb::Error> {
+        if let Err(err) = &result {
+            if is_transport_error(err) {
+                self.mark_broken();
+            }
+        }
+        result
+    }
+
+    /// Forwards to [`Surreal::use_ns`].
+    pub fn use_ns(&self, ns: impl Into<String>) -> UseNs<'_, Any> {
+        self.client.use_ns(ns)
+    }
+
+    /// Forwards to [`Surreal::use_db`].
+    pub fn use_db(&self, db: impl Into<String>) -> UseDb<'_, Any> {
+        self.client.use_db(db)
+    }
+
+    /// Forwards to [`Surreal::set`].
+    pub fn set(
+        &self,
+        key: impl Into<String>,
+        value: impl serde::Serialize + 'static,
+    ) -> surrealdb::method::Set<'_, Any> {
+        self.client.set(key, value)
+    }
+
+    /// Forwards to [`Surreal::unset`].
+    pub fn unset(&self, key: impl Into<String>) -> Unset<'_, Any> {
+        self.client.unset(key)
+    }
+
+    /// Forwards to [`Surreal::signup`].
+    pub fn signup<R>(&self, credentials: impl Credentials<auth::Signup, R>) -> Signup<'_, Any, R> {
+        self.client.signup(credentials)
+    }
+
+    /// Forwards to [`Surreal::signin`].
+    pub fn signin<R>(&self, credentials: impl Credentials<auth::Signin, R>) -> Signin<'_, Any, R> {
+        self.client.signin(credentials)
+    }
+
+    /// Forwards to [`Surreal::invalidate`].
+    pub fn invalidate(&self) -> Invalidate<'_, Any> {
+        self.client.invalidate()
+    }
+
+    /// Forwards to [`Surreal::authenticate`].
+    pub fn authenticate(&self, token: impl Into<Jwt>) -> Authenticate<'_, Any> {
+        self.client.authenticate(token)
+    }
+
+    /// Forwards to [`Surreal::query`].
+    pub fn query(&self, query: impl IntoQuery) -> Query<'_, Any> {
+        self.client.query(query)
+    }
+
+    /// Forwards to [`Surreal::select`].
+    pub fn select<O>(&self, resource: impl IntoResource<O>) -> Select<'_, Any, O> {
+        self.client.select(resource)
+    }
+
+    /// Forwards to [`Surreal::create`].
+    pub fn create<R>(&self, resource: impl CreateResource<R>) -> Create<'_, Any, R> {
+        self.client.create(resource)
+    }
+
+    /// Forwards to [`Surreal::insert`].
+    pub fn insert<O>(&self, resource: impl IntoResource<O>) -> Insert<'_, Any, O> {
+        self.client.insert(resource)
+    }
+
+    /// Forwards to [`Surreal::upsert`].
+    pub fn upsert<O>(&self, resource: impl IntoResource<O>) -> Upsert<'_, Any, O> {
+        self.client.upsert(resource)
+    }
+
+    /// Forwards to [`Surreal::update`].
+    pub fn update<O>(&self, resource: impl IntoResource<O>) -> Update<'_, Any, O> {
+        self.client.update(resource)
+    }
+
+    /// Forwards to [`Surreal::delete`].
+    pub fn delete<O>(&self, resource: impl IntoResource<O>) -> Delete<'_, Any, O> {
+        self.client.delete(resource)
+    }
+
+    /// Forwards to [`Surreal::version`].
+    pub fn version(&self) -> Version<'_, Any> {
+        self.client.version()
+    }
+
+    /// Forwards to [`Surreal::run`].
+    pub fn run<R>(&self, function: impl IntoFn) -> Run<'_, Any, R> {
+        self.client.run(function)
+    }
+
+    /// Forwards to [`Surreal::health`].
+    pub fn health(&self) -> Health<'_, Any> {
+        self.client.health()
+    }
+
+    /// Forwards to [`Surreal::wait_for`].
+    pub async fn wait_for(&self, event: WaitFor) {
+        self.client.wait_for(event).await
+    }
+
+    /// Forwards to [`Surreal::export`].
+    pub fn export<R>(&self, target: impl IntoExportDestination<R>) -> Export<'_, Any, R> {
+        self.client.export(target)
+    }
+
+    /// Forwards to [`Surreal::import`].
+    pub fn import(&self, file: impl AsRef<Path>) -> Import<'_, Any> {
+        self.client.import(file)
+    }
+
+    pub(crate) fn client(&self) -> &Surreal<Any> {
+        &self.client
+    }
+
+    pub(crate) fn mark_returned(&mut self) {
+        self.use_count += 1;
+    }
+
+    pub(crate) fn mark_checked(&mut self) {
+        self.last_checked = Some(Instant::now());
+    }
+}
+
+impl fmt::Debug for PooledSurreal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledSurreal")
+            .field("id", &self.id)
+            .field("created_at", &self.created_at)
+            .field("use_count", &self.use_count)
+            .field("last_checked", &self.last_checked)
+            .field("broken", &self.is_broken())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns `true` for errors that mean the connection itself failed, as
+/// opposed to a query, permission or authentication failure.
+pub(crate) fn is_transport_error(err: &surrealdb::Error) -> bool {
+    match err {
+        surrealdb::Error::Api(Api::Ws(_)) => !session::is_auth_error(err),
+        surrealdb::Error::Api(Api::ConnectionUninitialised) => true,
+        _ => false,
+    }
+}
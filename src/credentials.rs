@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use surrealdb::engine::any::Any;
+use surrealdb::opt::auth;
+use surrealdb::sql::Value;
+use surrealdb::Surreal;
+
+use crate::error::ConfigError;
+use crate::secret::Secret;
+
+/// The identity a pooled connection signs in with.
+///
+/// Anything below [`Root`](Credentials::Root) lets services pool connections
+/// with least-privilege users instead of sharing root credentials.
+#[derive(Clone)]
+pub enum Credentials {
+    /// A root user, with access to every namespace.
+    Root { username: String, password: Secret },
+    /// A user defined on a namespace.
+    Namespace {
+        namespace: String,
+        username: String,
+        password: Secret,
+    },
+    /// A user defined on a database.
+    Database {
+        namespace: String,
+        database: String,
+        username: String,
+        password: Secret,
+    },
+    /// A record user, signed in through an access method with arbitrary params.
+    Record {
+        namespace: String,
+        database: String,
+        access: String,
+        params: BTreeMap<String, Value>,
+    },
+    /// A pre-issued token, used to `authenticate` instead of signing in.
+    Token(Secret),
+}
+
+impl Credentials {
+    /// Credentials for a root user.
+    pub fn root(username: impl Into<String>, password: impl Into<Secret>) -> Self {
+        Credentials::Root {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Credentials for a namespace user.
+    pub fn namespace(
+        namespace: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<Secret>,
+    ) -> Self {
+        Credentials::Namespace {
+            namespace: namespace.into(),
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Credentials for a database user.
+    pub fn database(
+        namespace: impl Into<String>,
+        database: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<Secret>,
+    ) -> Self {
+        Credentials::Database {
+            namespace: namespace.into(),
+            database: database.into(),
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Credentials for a record user signing in through the `access` method.
+    pub fn record<K, V>(
+        namespace: impl Into<String>,
+        database: impl Into<String>,
+        access: impl Into<String>,
+        params: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        Credentials::Record {
+            namespace: namespace.into(),
+            database: database.into(),
+            access: access.into(),
+            params: params
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        }
+    }
+
+    /// A pre-issued token (JWT).
+    pub fn token(token: impl Into<Secret>) -> Self {
+        Credentials::Token(token.into())
+    }
+
+    /// Checks that no identifying field is empty.
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let fields: &[(&'static str, &str)] = match self {
+            Credentials::Root { username, .. } => &[("username", username)],
+            Credentials::Namespace {
+                namespace,
+                username,
+                ..
+            } => &[("namespace", namespace), ("username", username)],
+            Credentials::Database {
+                namespace,
+                database,
+                username,
+                ..
+            } => &[
+                ("namespace", namespace),
+                ("database", database),
+                ("username", username),
+            ],
+            Credentials::Record {
+                namespace,
+                database,
+                access,
+                ..
+            } => &[
+                ("namespace", namespace),
+                ("database", database),
+                ("access", access),
+            ],
+            Credentials::Token(token) => &[("token", token.expose())],
+        };
+        match fields.iter().find(|(_, value)| value.trim().is_empty()) {
+            Some((field, _)) => Err(ConfigError::EmptyField(field)),
+            None => Ok(()),
+        }
+    }
+
+    /// Signs the connection in with these credentials, or authenticates it
+    /// with the token.
+    pub(crate) async fn signin(&self, db: &Surreal<Any>) -> Result<(), surrealdb::Error> {
+        match self {
+            Credentials::Root { username, password } => {
+                db.signin(auth::Root {
+                    username,
+                    password: password.expose(),
+                })
+                .await?;
+            }
+            Credentials::Namespace {
+                namespace,
+                username,
+                password,
+            } => {
+                db.signin(auth::Namespace {
+                    namespace,
+                    username,
+                    password: password.expose(),
+                })
+                .await?;
+            }
+            Credentials::Database {
+                namespace,
+                database,
+                username,
+                password,
+            } => {
+                db.signin(auth::Database {
+                    namespace,
+                    database,
+                    username,
+                    password: password.expose(),
+                })
+                .await?;
+            }
+            Credentials::Record {
+                namespace,
+                database,
+                access,
+                params,
+            } => {
+                db.signin(auth::Record {
+                    namespace,
+                    database,
+                    access,
+                    params,
+                })
+                .await?;
+            }
+            Credentials::Token(token) => {
+                db.authenticate(token.expose()).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Credentials::Root { username, password } => f
+                .debug_struct("Root")
+                .field("username", username)
+                .field("password", password)
+                .finish(),
+            Credentials::Namespace {
+                namespace,
+                username,
+                password,
+            } => f
+                .debug_struct("Namespace")
+                .field("namespace", namespace)
+                .field("username", username)
+                .field("password", password)
+                .finish(),
+            Credentials::Database {
+                namespace,
+                database,
+                username,
+                password,
+            } => f
+                .debug_struct("Database")
+                .field("namespace", namespace)
+                .field("database", database)
+                .field("username", username)
+                .field("password", password)
+                .finish(),
+            // Record params routinely carry passwords, so only their names are shown.
+            Credentials::Record {
+                namespace,
+                database,
+                access,
+                params,
+            } => f
+                .debug_struct("Record")
+                .field("namespace", namespace)
+                .field("database", database)
+                .field("access", access)
+                .field("params", &params.keys().collect::<Vec<_>>())
+                .finish(),
+            Credentials::Token(token) => f.debug_tuple("Token").field(token).finish(),
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::env::{self, VarError};
+use std::mem;
+
+use mobc::async_trait;
+
+use crate::credentials::Credentials;
+use crate::error::{BoxError, ConfigError};
+use crate::protocol::protocol_from_scheme;
+use crate::provider::CredentialProvider;
+use crate::{ConnectionProtocol, SurrealDBConnectionManager};
+
+/// Prefix used by [`SurrealDBConnectionManager::from_env`] in the common case.
+pub const DEFAULT_ENV_PREFIX: &str = "SURREAL";
+
+impl SurrealDBConnectionManager {
+    /// Builds a manager from environment variables named `{prefix}_*`:
+    ///
+    /// | Variable            | Setting                                   |
+    /// |---------------------|-------------------------------------------|
+    /// | `{prefix}_URL`      | server address or datastore path          |
+    /// | `{prefix}_USER`     | username (required for remote servers)    |
+    /// | `{prefix}_PASS`     | password (required for remote servers)    |
+    /// | `{prefix}_TOKEN`    | pre-issued token, used instead of signin  |
+    /// | `{prefix}_NS`       | namespace                                 |
+    /// | `{prefix}_DB`       | database                                  |
+    /// | `{prefix}_PROTOCOL` | protocol, e.g. `ws`, `wss` or `mem`       |
+    ///
+    /// `{prefix}_URL` may also carry the scheme (`ws://127.0.0.1:8000`,
+    /// `rocksdb:///var/lib/surreal`), in which case `{prefix}_PROTOCOL` can be
+    /// left unset. It is only optional for the in-memory engine. Empty variables are
+    /// treated as unset. Every missing or malformed variable is reported at
+    /// once.
+    pub fn from_env(prefix: &str) -> Result<Self, ConfigError> {
+        let mut vars = EnvVars::new(prefix);
+
+        let url = vars.optional("URL");
+        let namespace = vars.optional("NS");
+        let database = vars.optional("DB");
+        let protocol = vars
+            .optional("PROTOCOL")
+            .and_then(|value| vars.parse("PROTOCOL", &value, str::parse::<ConnectionProtocol>));
+
+        // The address may carry its own scheme; it must agree with `{prefix}_PROTOCOL`.
+        let (scheme, address) = match url.as_deref().and_then(|url| url.split_once("://")) {
+            Some((scheme, address)) => (
+                Some(scheme.to_owned()),
+                Some(address.to_owned()).filter(|address| !address.is_empty()),
+            ),
+            None => (None, url),
+        };
+        let protocol = match scheme {
+            Some(scheme) => match (protocol, vars.parse("URL", &scheme, protocol_from_scheme)) {
+                (Some(protocol), Some(from_url))
+                    if mem::discriminant(&protocol) != mem::discriminant(&from_url) =>
+                {
+                    vars.malformed("URL");
+                    None
+                }
+                (protocol, from_url) => from_url.or(protocol),
+            },
+            None => protocol,
+        };
+
+        // Embedded engines need neither an address nor credentials; on-disk
+        // ones take their datastore path from `{prefix}_URL` unless
+        // `{prefix}_PROTOCOL` already names one.
+        let embedded = protocol
+            .as_ref()
+            .is_some_and(ConnectionProtocol::is_embedded);
+        let (protocol, address) = match (protocol, address) {
+            (Some(protocol), Some(path)) if protocol.datastore_path().is_some() => {
+                (Some(protocol.with_datastore_path(path)), None)
+            }
+            (Some(protocol), None)
+                if protocol
+                    .datastore_path()
+                    .is_some_and(|path| path.as_os_str().is_empty()) =>
+            {
+                vars.missing("URL");
+                (None, None)
+            }
+            (protocol, address) => {
+                if address.is_none() && !embedded {
+                    vars.missing("URL");
+                }
+                (protocol, address)
+            }
+        };
+        let credentials = vars.credentials(!embedded);
+
+        vars.finish()?;
+
+        let mut builder = Self::builder().protocol(protocol.unwrap_or_default());
+        if let Some(address) = address {
+            builder = builder.address(address);
+        }
+        if let Some(credentials) = credentials {
+            builder = builder.credentials(credentials);
+        }
+        if let Some(namespace) = namespace {
+            builder = builder.namespace(namespace);
+        }
+        if let Some(database) = database {
+            builder = builder.database(database);
+        }
+        builder.build()
+    }
+}
+
+/// A [`CredentialProvider`] that reads `{prefix}_TOKEN`, or `{prefix}_USER`
+/// and `{prefix}_PASS` for a root user, each time a connection is opened.
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    prefix: String,
+}
+
+impl EnvCredentials {
+    /// Creates a provider reading variables named `{prefix}_*`.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for EnvCredentials {
+    async fn credentials(&self) -> Result<Credentials, BoxError> {
+        let mut vars = EnvVars::new(&self.prefix);
+        let credentials = vars.credentials(true);
+        vars.finish()?;
+        Ok(credentials.ok_or(ConfigError::MissingField("credentials"))?)
+    }
+}
+
+/// Reads prefixed variables and collects every problem along the way.
+struct EnvVars<'a> {
+    prefix: &'a str,
+    missing: Vec<String>,
+    malformed: Vec<String>,
+}
+
+impl<'a> EnvVars<'a> {
+    fn new(prefix: &'a str) -> Self {
+        Self {
+            prefix,
+            missing: Vec::new(),
+            malformed: Vec::new(),
+        }
+    }
+
+    fn name(&self, suffix: &str) -> String {
+        if self.prefix.is_empty() {
+            suffix.to_owned()
+        } else {
+            format!("{}_{suffix}", self.prefix)
+        }
+    }
+
+    fn optional(&mut self, suffix: &str) -> Option<String> {
+        match env::var(self.name(suffix)) {
+            Ok(value) if value.trim().is_empty() => None,
+            Ok(value) => Some(value),
+            Err(VarError::NotPresent) => None,
+            Err(VarError::NotUnicode(_)) => {
+                self.malformed(suffix);
+                None
+            }
+        }
+    }
+
+    fn required(&mut self, suffix: &str) -> Option<String> {
+        let value = self.optional(suffix);
+        if value.is_none() {
+            self.missing(suffix);
+        }
+        value
+    }
+
+    /// Reads a token, or a root username and password. Unless `required`,
+    /// having neither is fine.
+    fn credentials(&mut self, required: bool) -> Option<Credentials> {
+        if let Some(token) = self.optional("TOKEN") {
+            return Some(Credentials::token(token));
+        }
+        if !required && self.optional("USER").is_none() && self.optional("PASS").is_none() {
+            return None;
+        }
+        let username = self.required("USER");
+        let password = self.required("PASS");
+        Some(Credentials::root(username?, password?))
+    }
+
+    fn parse<T, E>(
+        &mut self,
+        suffix: &str,
+        value: &str,
+        parse: impl Fn(&str) -> Result<T, E>,
+    ) -> Option<T> {
+        match parse(value) {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                self.malformed(suffix);
+                None
+            }
+        }
+    }
+
+    fn missing(&mut self, suffix: &str) {
+        let name = self.name(suffix);
+        if !self.malformed.contains(&name) {
+            self.missing.push(name);
+        }
+    }
+
+    fn malformed(&mut self, suffix: &str) {
+        let name = self.name(suffix);
+        if !self.malformed.contains(&name) {
+            self.malformed.push(name);
+        }
+    }
+
+    fn finish(self) -> Result<(), ConfigError> {
+        if self.missing.is_empty() && self.malformed.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Environment {
+                missing: self.missing,
+                malformed: self.malformed,
+            })
+        }
+    }
+}
+
+// The tests rely on `ws` being the default protocol.
+#[cfg(all(test, feature = "ws"))]
+mod tests {
+    use super::*;
+
+    // Every test uses its own prefix, as tests run in parallel.
+    fn set(prefix: &str, vars: &[(&str, &str)]) {
+        for (suffix, value) in vars {
+            env::set_var(format!("{prefix}_{suffix}"), value);
+        }
+    }
+
+    #[test]
+    fn from_env_reads_a_url_with_scheme_and_a_token() {
+        set(
+            "ENV_TEST_OK",
+            &[
+                ("URL", "ws://127.0.0.1:8000"),
+                ("TOKEN", "token"),
+                ("NS", "app"),
+            ],
+        );
+        let manager = SurrealDBConnectionManager::from_env("ENV_TEST_OK").unwrap();
+        assert_eq!(manager.protocol, ConnectionProtocol::Ws);
+        assert_eq!(manager.db_url, "127.0.0.1:8000");
+        assert_eq!(manager.db_namespace.as_deref(), Some("app"));
+        assert!(manager.credentials.is_some());
+    }
+
+    #[test]
+    fn from_env_reports_every_missing_variable() {
+        set("ENV_TEST_MISSING", &[("NS", "app")]);
+        let err = SurrealDBConnectionManager::from_env("ENV_TEST_MISSING").unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::Environment {
+                missing: vec![
+                    "ENV_TEST_MISSING_URL".to_owned(),
+                    "ENV_TEST_MISSING_USER".to_owned(),
+                    "ENV_TEST_MISSING_PASS".to_owned(),
+                ],
+                malformed: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn from_env_reports_missing_and_malformed_together() {
+        set(
+            "ENV_TEST_MALFORMED",
+            &[
+                ("URL", "127.0.0.1:8000"),
+                ("PROTOCOL", "gopher"),
+                ("USER", "root"),
+            ],
+        );
+        let err = SurrealDBConnectionManager::from_env("ENV_TEST_MALFORMED").unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::Environment {
+                missing: vec!["ENV_TEST_MALFORMED_PASS".to_owned()],
+                malformed: vec!["ENV_TEST_MALFORMED_PROTOCOL".to_owned()],
+            }
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn from_env_rejects_a_url_scheme_that_disagrees_with_the_protocol() {
+        set(
+            "ENV_TEST_MISMATCH",
+            &[
+                ("URL", "ws://127.0.0.1:8000"),
+                ("PROTOCOL", "http"),
+                ("TOKEN", "token"),
+            ],
+        );
+        let err = SurrealDBConnectionManager::from_env("ENV_TEST_MISMATCH").unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::Environment {
+                missing: Vec::new(),
+                malformed: vec!["ENV_TEST_MISMATCH_URL".to_owned()],
+            }
+        );
+    }
+
+    #[cfg(feature = "kv-mem")]
+    #[test]
+    fn from_env_reads_a_mem_url_without_an_address() {
+        set("ENV_TEST_MEM", &[("URL", "mem://")]);
+        let manager = SurrealDBConnectionManager::from_env("ENV_TEST_MEM").unwrap();
+        assert_eq!(manager.protocol, ConnectionProtocol::Mem);
+        assert_eq!(manager.db_url, "");
+    }
+}
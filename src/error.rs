@@ -0,0 +1,350 @@
+use std::fmt;
+use std::time::Duration;
+
+use surrealdb::error::Api;
+
+use crate::connection::is_transport_error;
+
+/// Errors reported while validating a manager configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// A required setting was never provided.
+    MissingField(&'static str),
+    /// A setting was provided but is empty.
+    EmptyField(&'static str),
+    /// A setting needs credentials, but none were configured.
+    RequiresCredentials(&'static str),
+    /// Two settings were given that exclude each other.
+    ConflictingSettings(&'static str, &'static str),
+    /// A database was selected without a namespace to hold it.
+    DatabaseWithoutNamespace,
+    /// A timeout was set to zero, which would fail every attempt.
+    ZeroTimeout(&'static str),
+    /// The server address is not a valid `host[:port][/path]`.
+    InvalidAddress { address: String, reason: String },
+    /// The server address carries a scheme that disagrees with the protocol.
+    SchemeMismatch { address: String, protocol: String },
+    /// TLS settings were rejected, e.g. a PEM holding no certificate, or TLS
+    /// settings given for a protocol that does not use TLS.
+    InvalidTls(String),
+    /// The retry policy is inconsistent, e.g. a zero attempt budget.
+    InvalidRetry(String),
+    /// The circuit breaker settings are inconsistent, e.g. a zero threshold.
+    InvalidCircuitBreaker(String),
+    /// A connection string could not be parsed as a URL.
+    InvalidUrl(String),
+    /// A connection string uses a scheme that maps to no known protocol.
+    UnsupportedScheme(String),
+    /// A protocol is known but its engine was not compiled in.
+    ProtocolNotEnabled(String),
+    /// A connection string path has more than namespace and database segments.
+    InvalidPath(String),
+    /// A percent-encoded component did not decode to valid UTF-8.
+    InvalidEncoding(&'static str),
+    /// A connection string query parameter is not recognised.
+    UnknownParameter(String),
+    /// A connection string sets a pool parameter where only manager settings
+    /// are read (see [`SurrealPoolConfig::from_url`](crate::SurrealPoolConfig::from_url)).
+    PoolParameter(String),
+    /// A connection string query parameter has a value that cannot be parsed.
+    InvalidParameter { name: String, value: String },
+    /// A setting cannot be used with an embedded engine, whose pooled
+    /// connections all share the datastore's single session.
+    Embedded {
+        setting: &'static str,
+        reason: &'static str,
+    },
+    /// Environment variables were missing or could not be parsed.
+    Environment {
+        missing: Vec<String>,
+        malformed: Vec<String>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingField(field) => write!(f, "missing required setting `{field}`"),
+            ConfigError::EmptyField(field) => write!(f, "setting `{field}` must not be empty"),
+            ConfigError::RequiresCredentials(setting) => {
+                write!(f, "`{setting}` requires credentials to sign in with")
+            }
+            ConfigError::ConflictingSettings(first, second) => {
+                write!(f, "`{first}` cannot be combined with `{second}`")
+            }
+            ConfigError::DatabaseWithoutNamespace => {
+                write!(f, "a database was set without a namespace; SurrealDB databases live inside a namespace")
+            }
+            ConfigError::ZeroTimeout(field) => {
+                write!(f, "timeout `{field}` must be greater than zero")
+            }
+            ConfigError::InvalidAddress { address, reason } => {
+                write!(f, "invalid server address `{address}`: {reason}")
+            }
+            ConfigError::SchemeMismatch { address, protocol } => write!(
+                f,
+                "server address `{address}` does not match protocol `{protocol}`; \
+                 leave the scheme out of the address and set the protocol instead"
+            ),
+            ConfigError::InvalidTls(reason) => write!(f, "invalid TLS configuration: {reason}"),
+            ConfigError::InvalidRetry(reason) => write!(f, "invalid retry policy: {reason}"),
+            ConfigError::InvalidCircuitBreaker(reason) => {
+                write!(f, "invalid circuit breaker: {reason}")
+            }
+            ConfigError::InvalidUrl(reason) => write!(f, "invalid connection string: {reason}"),
+            ConfigError::UnsupportedScheme(scheme) => {
+                write!(
+                    f,
+                    "unsupported scheme `{scheme}`; expected ws, wss, http, https, mem, rocksdb or surrealkv"
+                )
+            }
+            ConfigError::ProtocolNotEnabled(protocol) => write!(
+                f,
+                "protocol `{protocol}` is not enabled; enable the `ws` or `http` feature \
+                 (plus `rustls` or `native-tls` for wss/https), or `kv-mem`, `kv-rocksdb` \
+                 or `kv-surrealkv` for embedded engines"
+            ),
+            ConfigError::InvalidPath(path) => {
+                write!(
+                    f,
+                    "invalid path `{path}`; expected at most `/namespace/database`"
+                )
+            }
+            ConfigError::InvalidEncoding(field) => {
+                write!(f, "`{field}` is not valid percent-encoded UTF-8")
+            }
+            ConfigError::UnknownParameter(name) => {
+                write!(f, "unknown connection parameter `{name}`")
+            }
+            ConfigError::PoolParameter(name) => write!(
+                f,
+                "connection parameter `{name}` is a pool setting; \
+                 read the connection string with `SurrealPoolConfig::from_url` to apply it"
+            ),
+            ConfigError::InvalidParameter { name, value } => {
+                write!(
+                    f,
+                    "invalid value `{value}` for connection parameter `{name}`"
+                )
+            }
+            ConfigError::Embedded { setting, reason } => {
+                write!(
+                    f,
+                    "`{setting}` cannot be used with an embedded engine: {reason}"
+                )
+            }
+            ConfigError::Environment { missing, malformed } => {
+                let mut problems = Vec::new();
+                if !missing.is_empty() {
+                    problems.push(format!("missing: {}", missing.join(", ")));
+                }
+                if !malformed.is_empty() {
+                    problems.push(format!("malformed: {}", malformed.join(", ")));
+                }
+                write!(f, "invalid environment ({})", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A type-erased error, as returned by a [`CredentialProvider`](crate::CredentialProvider).
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors returned by [`SurrealDBConnectionManager`](crate::SurrealDBConnectionManager)
+/// when establishing or checking a connection.
+///
+/// Each variant names the phase that failed and wraps the client error that
+/// caused it, so callers can tell an unreachable server from rejected
+/// credentials or a missing database.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The settings used to connect are invalid, e.g. credentials supplied by
+    /// a provider with an empty username.
+    Config(ConfigError),
+    /// The credential provider could not supply credentials.
+    Credentials(BoxError),
+    /// The connection to the server could not be opened.
+    Dial(surrealdb::Error),
+    /// Signing in failed, e.g. because the credentials were rejected.
+    Auth(surrealdb::Error),
+    /// Selecting, verifying or creating the namespace and database failed.
+    Selection(surrealdb::Error),
+    /// The configured namespace does not exist (see [`SelectionCheck::Verify`](crate::SelectionCheck::Verify)).
+    NamespaceNotFound(String),
+    /// The configured database does not exist (see [`SelectionCheck::Verify`](crate::SelectionCheck::Verify)).
+    DatabaseNotFound { namespace: String, database: String },
+    /// Refreshing or resetting a pooled connection's session failed.
+    Session(surrealdb::Error),
+    /// The health check could not be run.
+    HealthCheck(surrealdb::Error),
+    /// The health check ran but did not get the expected answer.
+    HealthCheckMismatch(&'static str),
+    /// Establishing the connection took longer than the configured connect timeout.
+    ConnectTimeout(Duration),
+    /// Checking a pooled connection (`check()` as a whole) took longer than
+    /// the configured check timeout.
+    CheckTimeout(Duration),
+    /// Opening the connection to the server took longer than the configured dial timeout.
+    DialTimeout(Duration),
+    /// Signing in took longer than the configured signin timeout.
+    SigninTimeout(Duration),
+    /// Selecting the namespace and database took longer than the configured select timeout.
+    SelectTimeout(Duration),
+    /// A health check query (the probe or the session expiry lookup) took
+    /// longer than the configured health check timeout.
+    HealthCheckTimeout(Duration),
+    /// The circuit breaker is open, so no connection was attempted (see
+    /// [`CircuitBreaker`](crate::CircuitBreaker)).
+    CircuitOpen,
+    /// The manager's embedded datastore already has an open connection.
+    /// Embedded engines have a single session, so a pool over one must keep
+    /// `max_open` at 1.
+    EmbeddedInUse,
+}
+
+impl Error {
+    /// Returns `true` if a configured timeout expired.
+    pub fn is_timeout(&self) -> bool {
+        matches!(
+            self,
+            Error::ConnectTimeout(_)
+                | Error::CheckTimeout(_)
+                | Error::DialTimeout(_)
+                | Error::SigninTimeout(_)
+                | Error::SelectTimeout(_)
+                | Error::HealthCheckTimeout(_)
+        )
+    }
+
+    /// Returns `true` if the failure may be transient, so trying again later
+    /// can succeed: the server could not be reached, the connection dropped
+    /// while signing in, or dialing or signing in timed out. Rejected
+    /// credentials and invalid settings are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Dial(err) => {
+                is_transport_error(err) || matches!(err, surrealdb::Error::Api(Api::Http(_)))
+            }
+            Error::Auth(err) => is_transport_error(err),
+            Error::DialTimeout(_) | Error::SigninTimeout(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The client error behind this one, if any.
+    pub fn surreal_error(&self) -> Option<&surrealdb::Error> {
+        match self {
+            Error::Dial(err)
+            | Error::Auth(err)
+            | Error::Selection(err)
+            | Error::Session(err)
+            | Error::HealthCheck(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Config(err) => write!(f, "invalid connection settings: {err}"),
+            Error::Credentials(err) => write!(f, "failed to obtain credentials: {err}"),
+            Error::Dial(err) => write!(f, "failed to connect to SurrealDB: {err}"),
+            Error::Auth(err) => write!(f, "failed to sign in to SurrealDB: {err}"),
+            Error::Selection(err) => {
+                write!(f, "failed to select the namespace and database: {err}")
+            }
+            Error::NamespaceNotFound(namespace) => {
+                write!(f, "namespace `{namespace}` does not exist")
+            }
+            Error::DatabaseNotFound {
+                namespace,
+                database,
+            } => write!(
+                f,
+                "database `{database}` does not exist in namespace `{namespace}`"
+            ),
+            Error::Session(err) => write!(f, "failed to restore the session: {err}"),
+            Error::HealthCheck(err) => write!(f, "SurrealDB health check failed: {err}"),
+            Error::HealthCheckMismatch(reason) => {
+                write!(f, "SurrealDB health check failed: {reason}")
+            }
+            Error::ConnectTimeout(limit) => {
+                write!(f, "connecting to SurrealDB timed out after {limit:?}")
+            }
+            Error::CheckTimeout(limit) => {
+                write!(f, "SurrealDB connection check timed out after {limit:?}")
+            }
+            Error::DialTimeout(limit) => {
+                write!(f, "dialing SurrealDB timed out after {limit:?}")
+            }
+            Error::SigninTimeout(limit) => {
+                write!(f, "signing in to SurrealDB timed out after {limit:?}")
+            }
+            Error::SelectTimeout(limit) => write!(
+                f,
+                "selecting the namespace and database timed out after {limit:?}"
+            ),
+            Error::HealthCheckTimeout(limit) => {
+                write!(f, "SurrealDB health check query timed out after {limit:?}")
+            }
+            Error::CircuitOpen => write!(
+                f,
+                "circuit breaker is open after repeated failures; not connecting to SurrealDB"
+            ),
+            Error::EmbeddedInUse => write!(
+                f,
+                "the embedded datastore already has an open connection; \
+                 set the pool's `max_open` to 1 for embedded engines"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Config(err) => Some(err),
+            Error::Credentials(err) => Some(err.as_ref()),
+            _ => self.surreal_error().map(|err| err as _),
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        Error::Config(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::error::Db;
+
+    fn ws(message: &str) -> surrealdb::Error {
+        surrealdb::Error::Api(Api::Ws(message.to_owned()))
+    }
+
+    #[test]
+    fn transport_failures_and_timeouts_are_retryable() {
+        assert!(Error::Dial(ws("connection refused")).is_retryable());
+        assert!(Error::Auth(ws("connection reset")).is_retryable());
+        assert!(Error::Dial(surrealdb::Error::Api(Api::Http("503".to_owned()))).is_retryable());
+        assert!(Error::DialTimeout(Duration::from_secs(1)).is_retryable());
+        assert!(Error::SigninTimeout(Duration::from_secs(1)).is_retryable());
+    }
+
+    #[test]
+    fn rejected_credentials_and_settings_are_not_retryable() {
+        assert!(!Error::Auth(surrealdb::Error::Db(Db::InvalidAuth)).is_retryable());
+        assert!(!Error::Auth(ws("There was a problem with authentication")).is_retryable());
+        assert!(!Error::Config(ConfigError::MissingField("address")).is_retryable());
+        // A connect timeout already bounds every retry of that connect.
+        assert!(!Error::ConnectTimeout(Duration::from_secs(1)).is_retryable());
+    }
+}
@@ -1,121 +1,741 @@
 // Import necessary traits and types from external crates
 use mobc::async_trait;
 use mobc::Manager;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use surrealdb::{Surreal, Error};
+use std::time::{Duration, Instant};
 use surrealdb::engine::any; // Enables runtime selection of engine
+use surrealdb::opt::QueryResult;
+use surrealdb::{Response, Surreal};
+use tokio::sync::{OnceCell, Semaphore};
 
-/// Enum representing the supported connection protocols.
-#[derive(Debug, Clone)]
-pub enum ConnectionProtocol {
-    Http,
-    Https,
-    Ws,
-    Wss,
+use breaker::Breaker;
+
+mod breaker;
+mod builder;
+mod config;
+mod connection;
+mod credentials;
+mod dsn;
+mod env;
+mod error;
+mod protocol;
+mod provider;
+mod retry;
+mod secret;
+mod session;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+mod tls;
+
+pub use breaker::{CircuitBreaker, CircuitState};
+pub use builder::SurrealDBConnectionManagerBuilder;
+pub use config::{CredentialsConfig, SurrealPoolConfig};
+pub use connection::PooledSurreal;
+pub use credentials::Credentials;
+pub use env::{EnvCredentials, DEFAULT_ENV_PREFIX};
+pub use error::{BoxError, ConfigError, Error};
+pub use protocol::ConnectionProtocol;
+pub use provider::{CachedCredentials, CredentialProvider, FileCredentials};
+pub use retry::RetryPolicy;
+pub use secret::Secret;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+pub use tls::{TlsBackend, TlsConfig};
+
+#[cfg(not(any(
+    feature = "ws",
+    feature = "http",
+    feature = "kv-mem",
+    feature = "kv-rocksdb",
+    feature = "kv-surrealkv"
+)))]
+compile_error!(
+    "mobc-surrealdb needs at least one engine feature: `ws`, `http`, `kv-mem`, `kv-rocksdb` or `kv-surrealkv`"
+);
+
+/// Policy used by `check()` to decide whether a pooled connection is still usable.
+///
+/// The strategies trade cost against strictness: `Health` and `Version` only
+/// show that the server answers, `Query` and `Probe` that it runs queries,
+/// and `Authenticated` that the session still holds its identity.
+#[derive(Debug, Clone, Default)]
+pub enum HealthCheck {
+    /// Run `RETURN 1` and expect `1` back (the default).
+    #[default]
+    Query,
+    /// Call the client's `health()` endpoint.
+    Health,
+    /// Ask the server for its version.
+    Version,
+    /// Run a custom SurrealQL probe and test its first result; built with
+    /// `HealthCheck::probe`.
+    Probe(Probe),
+    /// Check that the session still carries an identity (`$auth` or
+    /// `$token`), signing in again if it lost it. Requires credentials, as
+    /// an anonymous session never has an identity.
+    Authenticated,
+    /// Skip the check entirely and hand out connections as they are.
+    Disabled,
 }
 
-impl ConnectionProtocol {
-    /// Returns the scheme as a static string slice.
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            ConnectionProtocol::Http => "http://",
-            ConnectionProtocol::Https => "https://",
-            ConnectionProtocol::Ws => "ws://",
-            ConnectionProtocol::Wss => "wss://",
-        }
+impl HealthCheck {
+    /// Runs `query` and passes its first result, read as `T`, to `expect`;
+    /// the connection is healthy if `expect` returns `true`, e.g.
+    /// `HealthCheck::probe("SELECT * FROM migration:latest", |row: Option<Migration>| row.is_some())`.
+    pub fn probe<T, F>(query: impl Into<String>, expect: F) -> Self
+    where
+        T: DeserializeOwned + 'static,
+        usize: QueryResult<T>,
+        F: Fn(T) -> bool + Send + Sync + 'static,
+    {
+        HealthCheck::Probe(Probe {
+            query: query.into(),
+            expect: Arc::new(move |response| Ok(expect(response.take(0).map_err(Box::new)?))),
+        })
     }
 }
 
-/// A high‑performance SurrealDB connection manager using static string slices.
+/// Reads a probe's response and decides whether it is healthy.
+type Expect = dyn Fn(&mut Response) -> Result<bool, Box<surrealdb::Error>> + Send + Sync;
+
+/// A custom health-check query, built with `HealthCheck::probe`.
+#[derive(Clone)]
+pub struct Probe {
+    query: String,
+    expect: Arc<Expect>,
+}
+
+impl fmt::Debug for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Probe")
+            .field("query", &self.query)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What `connect()` does about the configured namespace and database beyond
+/// selecting them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelectionCheck {
+    /// Select them as configured (the default); a missing one only shows up
+    /// when it is first queried.
+    #[default]
+    Skip,
+    /// Fail `connect()` unless both exist. Listing namespaces needs a root
+    /// user and listing databases a root or namespace user; database and
+    /// record users cannot sign in to a missing database in the first place.
+    Verify,
+    /// Create whichever is missing (`DEFINE ... IF NOT EXISTS`). Needs a user
+    /// allowed to define them.
+    Create,
+}
+
+/// A high‑performance SurrealDB connection manager.
 /// The default connection protocol is WebSocket (ws), but users can override it.
+///
+/// All configuration is owned by the manager, so it can be loaded at runtime
+/// (environment, secret stores, config files) without leaking memory. The
+/// password is kept in a [`Secret`], so the manager can be logged safely.
+///
+/// Embedded engines (`Mem`, `RocksDb`, `SurrealKv`) are opened once per
+/// manager, and the datastore has a single session. The manager therefore
+/// hands out one connection at a time: while it is open, `connect()` fails
+/// with [`Error::EmbeddedInUse`], so a pool over an embedded engine must keep
+/// `max_open` at 1. On-disk datastores are locked by the engine, so use a
+/// single manager per path.
+#[derive(Debug)]
 pub struct SurrealDBConnectionManager {
-    protocol: ConnectionProtocol,       // The connection protocol; default is Ws.
-    db_url: &'static str,               // Server address (host:port/path)
-    db_user: &'static str,              // Username for authentication
-    db_password: &'static str,          // Password for authentication,
-    db_namespace: Option<&'static str>, // Namespace to use
-    db_database: Option<&'static str>,  // Database to use
+    protocol: ConnectionProtocol, // The connection protocol; default is Ws.
+    db_url: String,               // Server address (host:port/path)
+    // Supplies the identity to authenticate as
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    db_namespace: Option<String>,           // Namespace to use
+    db_database: Option<String>,            // Database to use
+    connect_timeout: Option<Duration>,      // Bound on establishing a connection
+    check_timeout: Option<Duration>,        // Bound on check() as a whole
+    dial_timeout: Option<Duration>,         // Bound on opening the connection
+    signin_timeout: Option<Duration>,       // Bound on a single signin
+    select_timeout: Option<Duration>,       // Bound on selecting the namespace/database
+    health_check_timeout: Option<Duration>, // Bound on each health check query
+    health_check: HealthCheck,              // How connections are checked on checkout
+    selection_check: SelectionCheck,        // Whether ns/db are verified or created on connect
+    session_refresh: Option<Duration>,      // Re-sign in when the session expires this soon
+    reset_session: bool,                    // Restore the baseline session on every check
+    reset_variables: Vec<String>,           // Session variables unset by a reset
+    retry: Option<RetryPolicy>,             // How failed dials and signins are retried
+    breaker: Option<Breaker>,               // Fails connects fast after repeated failures
+    embedded: OnceCell<Surreal<any::Any>>,  // Shared handle to an embedded datastore
+    embedded_lease: Arc<Semaphore>,         // Held by the one open embedded connection
+    next_id: AtomicU64,                     // Id of the next connection opened
+    // TLS settings for wss/https
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    tls: Option<surrealdb::opt::Config>,
 }
 
 impl SurrealDBConnectionManager {
-    /// Creates a new connection manager with the default protocol (ws).
+    /// Returns a builder with named setters for every setting.
+    pub fn builder() -> SurrealDBConnectionManagerBuilder {
+        SurrealDBConnectionManagerBuilder::new()
+    }
+
+    /// Creates a new connection manager with the default protocol (ws, or
+    /// http when the `ws` feature is disabled), signing in as a root user.
+    ///
+    /// The address may repeat the protocol's scheme (`ws://host:port`) but is
+    /// otherwise used as given; use [`try_new`](Self::try_new) to have it
+    /// validated up front.
     pub fn new(
-        db_url: &'static str,
-        db_user: &'static str,
-        db_password: &'static str,
-        db_namespace: Option<&'static str>,
-        db_database: Option<&'static str>,
+        db_url: impl Into<String>,
+        db_user: impl Into<String>,
+        db_password: impl Into<Secret>,
+        db_namespace: Option<&str>,
+        db_database: Option<&str>,
     ) -> Self {
-        Self {
-            protocol: ConnectionProtocol::Ws, // Default to ws
+        Self::new_with_protocol(
+            ConnectionProtocol::default(), // Default to ws
             db_url,
             db_user,
             db_password,
             db_namespace,
             db_database,
-        }
+        )
     }
 
-    /// Creates a new connection manager with a custom protocol.
+    /// Creates a new connection manager with a custom protocol, signing in as
+    /// a root user. The address may repeat the protocol's scheme but is
+    /// otherwise used as given.
     pub fn new_with_protocol(
         protocol: ConnectionProtocol,
-        db_url: &'static str,
-        db_user: &'static str,
-        db_password: &'static str,
-        db_namespace: Option<&'static str>,
-        db_database: Option<&'static str>,
+        db_url: impl Into<String>,
+        db_user: impl Into<String>,
+        db_password: impl Into<Secret>,
+        db_namespace: Option<&str>,
+        db_database: Option<&str>,
     ) -> Self {
         Self {
             protocol,
+            db_url: db_url.into(),
+            credentials: Some(Arc::new(Credentials::root(db_user, db_password))),
+            db_namespace: db_namespace.map(str::to_owned),
+            db_database: db_database.map(str::to_owned),
+            connect_timeout: None,
+            check_timeout: None,
+            dial_timeout: None,
+            signin_timeout: None,
+            select_timeout: None,
+            health_check_timeout: None,
+            health_check: HealthCheck::default(),
+            selection_check: SelectionCheck::default(),
+            session_refresh: None,
+            reset_session: false,
+            reset_variables: Vec::new(),
+            retry: None,
+            breaker: None,
+            embedded: OnceCell::new(),
+            embedded_lease: Arc::new(Semaphore::new(1)),
+            next_id: AtomicU64::new(0),
+            #[cfg(any(feature = "rustls", feature = "native-tls"))]
+            tls: None,
+        }
+    }
+
+    /// Like [`new`](Self::new), but validates the settings first, rejecting
+    /// e.g. an address whose scheme disagrees with the protocol.
+    pub fn try_new(
+        db_url: impl Into<String>,
+        db_user: impl Into<String>,
+        db_password: impl Into<Secret>,
+        db_namespace: Option<&str>,
+        db_database: Option<&str>,
+    ) -> Result<Self, ConfigError> {
+        Self::try_new_with_protocol(
+            ConnectionProtocol::default(),
             db_url,
             db_user,
             db_password,
             db_namespace,
             db_database,
+        )
+    }
+
+    /// Like [`new_with_protocol`](Self::new_with_protocol), but validates the
+    /// settings first.
+    pub fn try_new_with_protocol(
+        protocol: ConnectionProtocol,
+        db_url: impl Into<String>,
+        db_user: impl Into<String>,
+        db_password: impl Into<Secret>,
+        db_namespace: Option<&str>,
+        db_database: Option<&str>,
+    ) -> Result<Self, ConfigError> {
+        let mut builder = Self::builder()
+            .protocol(protocol)
+            .address(db_url)
+            .username(db_user)
+            .password(db_password);
+        if let Some(namespace) = db_namespace {
+            builder = builder.namespace(namespace);
+        }
+        if let Some(database) = db_database {
+            builder = builder.database(database);
         }
+        builder.build()
     }
-}
 
-#[async_trait]
-impl Manager for SurrealDBConnectionManager {
-    // Use Surreal with the 'any' engine for runtime flexibility.
-    type Connection = Arc<Surreal<any::Any>>;
-    type Error = Error;
+    /// The state of the circuit breaker, or `None` if none is configured.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.breaker.as_ref().map(Breaker::state)
+    }
 
-    /// Establish a new connection.
-    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+    /// Records the outcome of a connect or health check with the circuit
+    /// breaker, if any.
+    fn record<T>(&self, result: &Result<T, Error>) {
+        if let Some(breaker) = &self.breaker {
+            breaker.record(result.is_ok());
+        }
+    }
+
+    /// Opens a connection. Embedded engines are opened once and reused, as
+    /// every connection must see the same datastore; a reused handle is
+    /// signed in and reselected again, as its previous holder may have
+    /// changed the session.
+    async fn establish(&self) -> Result<Surreal<any::Any>, Error> {
+        if !self.protocol.is_embedded() {
+            return self.open().await;
+        }
+        if let Some(db) = self.embedded.get() {
+            self.reauthenticate(db).await?;
+            return Ok(db.clone());
+        }
+        let db = self.embedded.get_or_try_init(|| self.open()).await?;
+        Ok(db.clone())
+    }
+
+    /// Dials the server, signs in and selects the namespace/database.
+    async fn open(&self) -> Result<Surreal<any::Any>, Error> {
+        // Fetch credentials first, so rotated credentials apply to this connection.
+        let credentials = self.fetch_credentials().await?;
+
+        let db = self.dial_and_signin(credentials.as_ref()).await?;
+        within(self.select_timeout, Error::SelectTimeout, async {
+            self.select(&db).await?;
+            self.check_selection(&db).await
+        })
+        .await?;
+
+        Ok(db)
+    }
+
+    /// Dials the server and signs in, retrying transient failures as the
+    /// retry policy allows.
+    async fn dial_and_signin(
+        &self,
+        credentials: Option<&Credentials>,
+    ) -> Result<Surreal<any::Any>, Error> {
+        let started = Instant::now();
+        let mut attempt = 1;
+        loop {
+            let err = match self.try_dial_and_signin(credentials).await {
+                Ok(db) => return Ok(db),
+                Err(err) => err,
+            };
+            let backoff = self
+                .retry
+                .as_ref()
+                .filter(|_| err.is_retryable())
+                .and_then(|retry| retry.backoff(attempt, started.elapsed()));
+            match backoff {
+                Some(backoff) => tokio::time::sleep(backoff).await,
+                None => return Err(err),
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Makes a single attempt at dialing the server and signing in.
+    async fn try_dial_and_signin(
+        &self,
+        credentials: Option<&Credentials>,
+    ) -> Result<Surreal<any::Any>, Error> {
         // Construct the full URL by concatenating the protocol and the server address.
-        let full_url = format!("{}{}", self.protocol.as_str(), self.db_url);
-        let db = any::connect(full_url).await?;
+        let full_url = self.protocol.endpoint(&self.db_url);
+        let db = within(self.dial_timeout, Error::DialTimeout, self.dial(full_url)).await?;
         // Authenticate using the provided credentials.
-        db.signin(surrealdb::opt::auth::Root {
-            username: self.db_user,
-            password: self.db_password,
+        if let Some(credentials) = credentials {
+            self.signin(&db, credentials).await?;
+        }
+        Ok(db)
+    }
+
+    /// Opens the connection to the server, with the TLS settings if any.
+    async fn dial(&self, full_url: String) -> Result<Surreal<any::Any>, Error> {
+        #[cfg(any(feature = "rustls", feature = "native-tls"))]
+        if let Some(config) = &self.tls {
+            return any::connect((full_url, config.clone()))
+                .await
+                .map_err(Error::Dial);
+        }
+        any::connect(full_url).await.map_err(Error::Dial)
+    }
+
+    /// Signs the connection in, within the signin timeout.
+    async fn signin(&self, db: &Surreal<any::Any>, credentials: &Credentials) -> Result<(), Error> {
+        within(self.signin_timeout, Error::SigninTimeout, async {
+            credentials.signin(db).await.map_err(Error::Auth)
         })
-        .await?;
+        .await
+    }
 
-        if let Some(namespace) = self.db_namespace {
-            db.use_ns(namespace).await?;
+    /// Asks the credential provider for the identity to sign in as, if any.
+    async fn fetch_credentials(&self) -> Result<Option<Credentials>, Error> {
+        let Some(provider) = &self.credentials else {
+            return Ok(None);
+        };
+        let credentials = provider.credentials().await.map_err(Error::Credentials)?;
+        credentials.validate()?;
+        Ok(Some(credentials))
+    }
+
+    /// Selects the configured namespace and database, if any.
+    async fn select(&self, db: &Surreal<any::Any>) -> Result<(), Error> {
+        // The builder rejects a database without a namespace.
+        match (&self.db_namespace, &self.db_database) {
+            (Some(namespace), Some(database)) => db.use_ns(namespace).use_db(database).await,
+            (Some(namespace), None) => db.use_ns(namespace).await,
+            (None, _) => Ok(()),
+        }
+        .map_err(Error::Selection)
+    }
 
-            if let Some(database) = self.db_database {
-                db.use_db(database).await?;
+    /// Verifies or creates the selected namespace and database, as configured.
+    async fn check_selection(&self, db: &Surreal<any::Any>) -> Result<(), Error> {
+        let Some(namespace) = &self.db_namespace else {
+            return Ok(());
+        };
+        match self.selection_check {
+            SelectionCheck::Skip => {}
+            SelectionCheck::Verify => {
+                let exists = session::namespace_exists(db, namespace).await;
+                if !exists.map_err(Error::Selection)? {
+                    return Err(Error::NamespaceNotFound(namespace.clone()));
+                }
+                if let Some(database) = &self.db_database {
+                    let exists = session::database_exists(db, database).await;
+                    if !exists.map_err(Error::Selection)? {
+                        return Err(Error::DatabaseNotFound {
+                            namespace: namespace.clone(),
+                            database: database.clone(),
+                        });
+                    }
+                }
+            }
+            SelectionCheck::Create => {
+                session::define_selection(db, namespace, self.db_database.as_deref())
+                    .await
+                    .map_err(Error::Selection)?
             }
         }
+        Ok(())
+    }
 
-        // Return the connection wrapped in an Arc.
-        Ok(Arc::new(db))
+    /// Signs an existing connection in again with fresh credentials.
+    async fn reauthenticate(&self, conn: &Surreal<any::Any>) -> Result<(), Error> {
+        if let Some(credentials) = self.fetch_credentials().await? {
+            self.signin(conn, &credentials).await?;
+        }
+        within(self.select_timeout, Error::SelectTimeout, self.select(conn)).await
     }
 
-    /// Check the health of an existing connection.
-    async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
-        let mut response = conn.query("RETURN 1").await?;
-        let result: Option<i32> = response.take(0)?;
+    /// Restores the baseline session a borrower may have changed: unsets the
+    /// configured variables, drops whatever identity is signed in, signs in
+    /// again as the configured one and reselects the namespace and database.
+    async fn reset(&self, conn: &Surreal<any::Any>) -> Result<(), Error> {
+        for name in &self.reset_variables {
+            conn.unset(name).await.map_err(Error::Session)?;
+        }
+        if self.credentials.is_some() {
+            conn.invalidate().await.map_err(Error::Session)?;
+        }
+        self.reauthenticate(conn).await
+    }
+
+    /// Resets or verifies a connection before it is handed out again.
+    async fn recycle(&self, conn: &Surreal<any::Any>) -> Result<(), Error> {
+        match self.reset_session {
+            // The reset signs in again, so only the health check itself is left.
+            true => {
+                self.reset(conn).await?;
+                within(
+                    self.health_check_timeout,
+                    Error::HealthCheckTimeout,
+                    self.probe(conn),
+                )
+                .await
+            }
+            false => self.verify_or_reauthenticate(conn).await,
+        }
+    }
+
+    /// Refreshes a session that is about to expire, then runs the health check.
+    async fn verify(&self, conn: &Surreal<any::Any>) -> Result<(), Error> {
+        // Embedded sessions do not expire, so there is nothing to refresh.
+        if let Some(margin) = self
+            .session_refresh
+            .filter(|_| !self.protocol.is_embedded())
+        {
+            let expiring = within(
+                self.health_check_timeout,
+                Error::HealthCheckTimeout,
+                async {
+                    session::expires_within(conn, margin)
+                        .await
+                        .map_err(Error::Session)
+                },
+            )
+            .await?;
+            if expiring {
+                self.reauthenticate(conn).await?;
+            }
+        }
+
+        within(
+            self.health_check_timeout,
+            Error::HealthCheckTimeout,
+            self.probe(conn),
+        )
+        .await
+    }
+
+    /// Runs the configured health check.
+    async fn probe(&self, conn: &Surreal<any::Any>) -> Result<(), Error> {
+        match &self.health_check {
+            HealthCheck::Disabled => Ok(()),
+            HealthCheck::Query => self.ping(conn).await,
+            HealthCheck::Health => conn.health().await.map_err(Error::HealthCheck),
+            HealthCheck::Version => match conn.version().await {
+                Ok(_) => Ok(()),
+                Err(err) => Err(Error::HealthCheck(err)),
+            },
+            HealthCheck::Probe(probe) => {
+                let mut response = conn
+                    .query(probe.query.as_str())
+                    .await
+                    .map_err(Error::HealthCheck)?;
+                match (probe.expect)(&mut response).map_err(|err| Error::HealthCheck(*err))? {
+                    true => Ok(()),
+                    false => Err(Error::HealthCheckMismatch("the probe did not pass")),
+                }
+            }
+            HealthCheck::Authenticated => {
+                let mut response = conn
+                    .query("RETURN $auth != NONE OR $token != NONE")
+                    .await
+                    .map_err(Error::HealthCheck)?;
+                let authenticated: Option<bool> = response.take(0).map_err(Error::HealthCheck)?;
+                match authenticated {
+                    Some(true) => Ok(()),
+                    _ => Err(Error::HealthCheckMismatch("the session has no identity")),
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if `err` means the session expired or lost its
+    /// identity, so signing in again may fix the connection.
+    fn session_lost(&self, err: &Error) -> bool {
+        match err {
+            Error::Session(err) | Error::HealthCheck(err) => session::is_auth_error(err),
+            Error::HealthCheckMismatch(_) => {
+                matches!(self.health_check, HealthCheck::Authenticated)
+            }
+            _ => false,
+        }
+    }
+
+    /// Verifies the connection, signing in again once if the session has
+    /// expired or lost its identity.
+    async fn verify_or_reauthenticate(&self, conn: &Surreal<any::Any>) -> Result<(), Error> {
+        match self.verify(conn).await {
+            Err(err) if self.session_lost(&err) => {
+                self.reauthenticate(conn).await?;
+                self.verify(conn).await
+            }
+            result => result,
+        }
+    }
+
+    /// Runs `RETURN 1` on the connection and expects `1` back.
+    async fn ping(&self, conn: &Surreal<any::Any>) -> Result<(), Error> {
+        let mut response = conn.query("RETURN 1").await.map_err(Error::HealthCheck)?;
+        let result: Option<i32> = response.take(0).map_err(Error::HealthCheck)?;
         if result == Some(1) {
-            Ok(conn)
+            Ok(())
         } else {
-            Err(Error::Api(surrealdb::error::Api::Query(
-                "Health check failed".into(),
-            )))
+            Err(Error::HealthCheckMismatch("`RETURN 1` did not return 1"))
         }
     }
 }
+
+/// Runs `future`, failing with `elapsed(limit)` if it is still running once
+/// `limit` has passed.
+async fn within<T>(
+    limit: Option<Duration>,
+    elapsed: fn(Duration) -> Error,
+    future: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, future)
+            .await
+            .map_err(|_| elapsed(limit))?,
+        None => future.await,
+    }
+}
+
+#[async_trait]
+impl Manager for SurrealDBConnectionManager {
+    // Use Surreal with the 'any' engine for runtime flexibility.
+    type Connection = PooledSurreal;
+    type Error = Error;
+
+    /// Establish a new connection.
+    ///
+    /// Fails fast with [`Error::CircuitOpen`] while the circuit breaker is
+    /// open, and with [`Error::EmbeddedInUse`] while an embedded datastore's
+    /// connection is open.
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let lease = match self.protocol.is_embedded() {
+            true => match Arc::clone(&self.embedded_lease).try_acquire_owned() {
+                Ok(lease) => Some(lease),
+                Err(_) => return Err(Error::EmbeddedInUse),
+            },
+            false => None,
+        };
+        if self
+            .breaker
+            .as_ref()
+            .is_some_and(|breaker| !breaker.try_acquire())
+        {
+            return Err(Error::CircuitOpen);
+        }
+        let result = within(
+            self.connect_timeout,
+            Error::ConnectTimeout,
+            self.establish(),
+        )
+        .await;
+        self.record(&result);
+        let db = result?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        Ok(PooledSurreal::new(id, db, lease))
+    }
+
+    /// Check the health of an existing connection.
+    ///
+    /// A connection whose session expired is signed in again in place; if
+    /// that fails too, the error makes the pool discard the connection. With
+    /// session resets enabled the session is restored to its baseline before
+    /// the health check runs.
+    async fn check(&self, mut conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
+        let result = within(
+            self.check_timeout,
+            Error::CheckTimeout,
+            self.recycle(conn.client()),
+        )
+        .await;
+        self.record(&result);
+        result?;
+        conn.mark_checked();
+        Ok(conn)
+    }
+
+    /// Counts the use that just ended and discards connections flagged as
+    /// broken. Runs on every return to the pool, without any I/O.
+    fn validate(&self, conn: &mut Self::Connection) -> bool {
+        conn.mark_returned();
+        !conn.is_broken()
+    }
+}
+
+#[cfg(all(test, feature = "kv-mem"))]
+mod tests {
+    use super::*;
+
+    fn embedded() -> SurrealDBConnectionManagerBuilder {
+        SurrealDBConnectionManager::builder()
+            .protocol(ConnectionProtocol::Mem)
+            .namespace("a")
+            .database("b")
+    }
+
+    #[tokio::test]
+    async fn embedded_checkouts_share_the_datastore() {
+        let manager = embedded().build().unwrap();
+        let pool = mobc::Pool::builder().max_open(1).build(manager);
+
+        let conn = pool.get().await.unwrap();
+        conn.query("CREATE item:1 SET n = 1").await.unwrap();
+        drop(conn);
+
+        let conn = pool.get().await.unwrap();
+        let mut response = conn.query("SELECT VALUE n FROM item").await.unwrap();
+        let rows: Vec<i64> = response.take(0).unwrap();
+        assert_eq!(rows, [1]);
+    }
+
+    #[tokio::test]
+    async fn embedded_engines_open_one_connection_at_a_time() {
+        let manager = embedded().build().unwrap();
+
+        let conn = manager.connect().await.unwrap();
+        let err = manager.connect().await.unwrap_err();
+        assert!(matches!(err, Error::EmbeddedInUse), "{err:?}");
+
+        drop(conn);
+        manager.connect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reset_session_restores_embedded_baseline() {
+        let manager = embedded()
+            .reset_session(true)
+            .reset_variables(["x"])
+            .build()
+            .unwrap();
+        let pool = mobc::Pool::builder().max_open(1).build(manager);
+
+        let conn = pool.get().await.unwrap();
+        conn.set("x", 5).await.unwrap();
+        conn.use_ns("other").use_db("other").await.unwrap();
+        drop(conn);
+
+        let conn = pool.get().await.unwrap();
+        let mut response = conn
+            .query("RETURN $x; RETURN session::ns(); RETURN session::db()")
+            .await
+            .unwrap();
+        let x: Option<i64> = response.take(0).unwrap();
+        let ns: Option<String> = response.take(1).unwrap();
+        let db: Option<String> = response.take(2).unwrap();
+        assert_eq!(x, None);
+        assert_eq!(ns.as_deref(), Some("a"));
+        assert_eq!(db.as_deref(), Some("b"));
+    }
+
+    #[tokio::test]
+    async fn reset_session_still_runs_the_health_check() {
+        let manager = embedded()
+            .reset_session(true)
+            .health_check(HealthCheck::probe("RETURN false", |ok: Option<bool>| {
+                ok == Some(true)
+            }))
+            .build()
+            .unwrap();
+
+        let conn = manager.connect().await.unwrap();
+        let err = manager.check(conn).await.unwrap_err();
+        assert!(matches!(err, Error::HealthCheckMismatch(_)), "{err:?}");
+    }
+}
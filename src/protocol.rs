@@ -0,0 +1,348 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::ConfigError;
+
+/// Optional prefix marking a URL as a SurrealDB connection string.
+const SCHEME_PREFIX: &str = "surrealdb+";
+
+/// Enum representing the supported connection protocols.
+///
+/// Each variant only exists when the matching SurrealDB engine is compiled in:
+/// `Ws`/`Http` need the `ws`/`http` features, and `Wss`/`Https` additionally
+/// need a TLS backend (`rustls` or `native-tls`); `Mem`, `RocksDb` and
+/// `SurrealKv` need `kv-mem`, `kv-rocksdb` and `kv-surrealkv`. The default is
+/// `Ws`, or `Http` when the `ws` feature is disabled, or `Mem` in an embedded
+/// build. A build with only on-disk engines defaults to one without a path,
+/// which has to be given one before a manager can be built.
+///
+/// A protocol parses from and displays as its scheme (`"wss".parse()`), or,
+/// for on-disk engines, as a scheme and datastore path
+/// (`rocksdb:///var/lib/surreal`). With the `serde` feature it is
+/// (de)serialized in that same form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionProtocol {
+    #[cfg(feature = "http")]
+    Http,
+    #[cfg(all(feature = "http", any(feature = "rustls", feature = "native-tls")))]
+    Https,
+    #[cfg(feature = "ws")]
+    Ws,
+    #[cfg(all(feature = "ws", any(feature = "rustls", feature = "native-tls")))]
+    Wss,
+    /// An embedded in-memory datastore, shared by every connection of a manager.
+    #[cfg(feature = "kv-mem")]
+    Mem,
+    /// An embedded RocksDB datastore at the given path.
+    #[cfg(feature = "kv-rocksdb")]
+    RocksDb(PathBuf),
+    /// An embedded SurrealKV datastore at the given path.
+    #[cfg(feature = "kv-surrealkv")]
+    SurrealKv(PathBuf),
+}
+
+impl ConnectionProtocol {
+    /// Returns the scheme as a static string slice.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "http")]
+            ConnectionProtocol::Http => "http://",
+            #[cfg(all(feature = "http", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Https => "https://",
+            #[cfg(feature = "ws")]
+            ConnectionProtocol::Ws => "ws://",
+            #[cfg(all(feature = "ws", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Wss => "wss://",
+            #[cfg(feature = "kv-mem")]
+            ConnectionProtocol::Mem => "mem://",
+            #[cfg(feature = "kv-rocksdb")]
+            ConnectionProtocol::RocksDb(_) => "rocksdb://",
+            #[cfg(feature = "kv-surrealkv")]
+            ConnectionProtocol::SurrealKv(_) => "surrealkv://",
+        }
+    }
+
+    /// Returns the scheme name, e.g. `"wss"`.
+    pub fn scheme(&self) -> &'static str {
+        self.as_str().trim_end_matches("://")
+    }
+
+    /// Returns `true` when traffic to the server is encrypted with TLS
+    /// (`wss`, `https`).
+    pub fn is_secure(&self) -> bool {
+        match self {
+            #[cfg(all(feature = "http", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Https => true,
+            #[cfg(all(feature = "ws", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Wss => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Returns `true` when a connection carries a session of its own
+    /// (WebSocket and embedded engines), so signin, the selected
+    /// namespace/database and `LET` variables persist between queries and
+    /// live queries are available. Over HTTP every request stands alone.
+    pub fn is_stateful(&self) -> bool {
+        match self {
+            #[cfg(feature = "http")]
+            ConnectionProtocol::Http => false,
+            #[cfg(all(feature = "http", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Https => false,
+            #[allow(unreachable_patterns)]
+            _ => true,
+        }
+    }
+
+    /// Returns the port dialled when the address does not name one: 80 for
+    /// `ws`/`http` and 443 for `wss`/`https`. Embedded engines have no port.
+    ///
+    /// Note that `surreal start` listens on 8000 by default, so a local
+    /// server usually needs an explicit port.
+    pub fn default_port(&self) -> Option<u16> {
+        match self {
+            #[cfg(feature = "http")]
+            ConnectionProtocol::Http => Some(80),
+            #[cfg(all(feature = "http", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Https => Some(443),
+            #[cfg(feature = "ws")]
+            ConnectionProtocol::Ws => Some(80),
+            #[cfg(all(feature = "ws", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Wss => Some(443),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for engines that run inside this process.
+    pub(crate) fn is_embedded(&self) -> bool {
+        match self {
+            #[cfg(feature = "kv-mem")]
+            ConnectionProtocol::Mem => true,
+            #[cfg(feature = "kv-rocksdb")]
+            ConnectionProtocol::RocksDb(_) => true,
+            #[cfg(feature = "kv-surrealkv")]
+            ConnectionProtocol::SurrealKv(_) => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Replaces the datastore path of an on-disk embedded engine; other
+    /// protocols are returned unchanged.
+    #[cfg_attr(
+        not(any(feature = "kv-rocksdb", feature = "kv-surrealkv")),
+        allow(unused_variables)
+    )]
+    pub(crate) fn with_datastore_path(self, path: impl Into<PathBuf>) -> Self {
+        match self {
+            #[cfg(feature = "kv-rocksdb")]
+            ConnectionProtocol::RocksDb(_) => ConnectionProtocol::RocksDb(path.into()),
+            #[cfg(feature = "kv-surrealkv")]
+            ConnectionProtocol::SurrealKv(_) => ConnectionProtocol::SurrealKv(path.into()),
+            #[allow(unreachable_patterns)]
+            protocol => protocol,
+        }
+    }
+
+    /// Returns the datastore path of an on-disk embedded engine.
+    pub(crate) fn datastore_path(&self) -> Option<&Path> {
+        match self {
+            #[cfg(feature = "kv-rocksdb")]
+            ConnectionProtocol::RocksDb(path) => Some(path),
+            #[cfg(feature = "kv-surrealkv")]
+            ConnectionProtocol::SurrealKv(path) => Some(path),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Returns the URL handed to SurrealDB: the scheme followed by the server
+    /// address, or by the datastore path for on-disk engines.
+    pub(crate) fn endpoint(&self, address: &str) -> String {
+        match self.datastore_path() {
+            Some(path) => format!("{}{}", self.as_str(), path.display()),
+            // An address given to `new` may already carry the scheme.
+            None => {
+                let address = address.strip_prefix(self.as_str()).unwrap_or(address);
+                format!("{}{}", self.as_str(), address)
+            }
+        }
+    }
+}
+
+impl Default for ConnectionProtocol {
+    // Exactly one of these returns is compiled in first; the engine features
+    // are checked in lib.rs, so at least one is.
+    #[allow(unreachable_code)]
+    fn default() -> Self {
+        #[cfg(feature = "ws")]
+        return ConnectionProtocol::Ws;
+        #[cfg(feature = "http")]
+        return ConnectionProtocol::Http;
+        #[cfg(feature = "kv-mem")]
+        return ConnectionProtocol::Mem;
+        #[cfg(feature = "kv-rocksdb")]
+        return ConnectionProtocol::RocksDb(PathBuf::new());
+        #[cfg(feature = "kv-surrealkv")]
+        return ConnectionProtocol::SurrealKv(PathBuf::new());
+    }
+}
+
+impl fmt::Display for ConnectionProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.datastore_path() {
+            Some(_) => f.write_str(&self.endpoint("")),
+            None => f.write_str(self.scheme()),
+        }
+    }
+}
+
+impl FromStr for ConnectionProtocol {
+    type Err = ConfigError;
+
+    /// Accepts a scheme (`ws`, `surrealdb+wss`), a scheme with its separator
+    /// (`https://`), or an on-disk engine with its datastore path
+    /// (`rocksdb:///var/lib/surreal`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let Some((scheme, rest)) = s.split_once("://") else {
+            return protocol_from_scheme(s);
+        };
+        let protocol = protocol_from_scheme(scheme)?;
+        match protocol.datastore_path() {
+            Some(_) => Ok(protocol.with_datastore_path(rest)),
+            None if rest.is_empty() => Ok(protocol),
+            None => Err(ConfigError::InvalidUrl(format!(
+                "`{s}` is a URL, not a protocol; set the server address separately"
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ConnectionProtocol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ConnectionProtocol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Maps a URL scheme, with or without the `surrealdb+` prefix and in any
+/// case, onto a protocol.
+/// On-disk engines come back with an empty datastore path, to be filled in
+/// with [`ConnectionProtocol::with_datastore_path`].
+pub(crate) fn protocol_from_scheme(scheme: &str) -> Result<ConnectionProtocol, ConfigError> {
+    let scheme = scheme.to_ascii_lowercase();
+    match scheme.strip_prefix(SCHEME_PREFIX).unwrap_or(&scheme) {
+        #[cfg(feature = "ws")]
+        "ws" => Ok(ConnectionProtocol::Ws),
+        #[cfg(all(feature = "ws", any(feature = "rustls", feature = "native-tls")))]
+        "wss" => Ok(ConnectionProtocol::Wss),
+        #[cfg(feature = "http")]
+        "http" => Ok(ConnectionProtocol::Http),
+        #[cfg(all(feature = "http", any(feature = "rustls", feature = "native-tls")))]
+        "https" => Ok(ConnectionProtocol::Https),
+        #[cfg(feature = "kv-mem")]
+        "mem" | "memory" => Ok(ConnectionProtocol::Mem),
+        #[cfg(feature = "kv-rocksdb")]
+        "rocksdb" => Ok(ConnectionProtocol::RocksDb(PathBuf::new())),
+        #[cfg(feature = "kv-surrealkv")]
+        "surrealkv" => Ok(ConnectionProtocol::SurrealKv(PathBuf::new())),
+        // Known protocols whose engine was not compiled in. Unreachable with
+        // every engine enabled.
+        #[allow(unreachable_patterns)]
+        protocol @ ("ws" | "wss" | "http" | "https" | "mem" | "memory" | "rocksdb"
+        | "surrealkv") => Err(ConfigError::ProtocolNotEnabled(protocol.to_owned())),
+        _ => Err(ConfigError::UnsupportedScheme(scheme)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every protocol compiled in, with a path for the on-disk engines.
+    fn protocols() -> Vec<ConnectionProtocol> {
+        vec![
+            #[cfg(feature = "http")]
+            ConnectionProtocol::Http,
+            #[cfg(all(feature = "http", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Https,
+            #[cfg(feature = "ws")]
+            ConnectionProtocol::Ws,
+            #[cfg(all(feature = "ws", any(feature = "rustls", feature = "native-tls")))]
+            ConnectionProtocol::Wss,
+            #[cfg(feature = "kv-mem")]
+            ConnectionProtocol::Mem,
+            #[cfg(feature = "kv-rocksdb")]
+            ConnectionProtocol::RocksDb("/var/lib/surreal".into()),
+            #[cfg(feature = "kv-surrealkv")]
+            ConnectionProtocol::SurrealKv("data/surreal kv".into()),
+        ]
+    }
+
+    #[test]
+    fn display_round_trips_through_from_str() {
+        for protocol in protocols() {
+            assert_eq!(protocol.to_string().parse(), Ok(protocol));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        for protocol in protocols() {
+            let json = serde_json::to_string(&protocol).unwrap();
+            assert_eq!(json, format!("\"{protocol}\""));
+            assert_eq!(
+                serde_json::from_str::<ConnectionProtocol>(&json).unwrap(),
+                protocol
+            );
+        }
+    }
+
+    #[cfg(feature = "ws")]
+    #[test]
+    fn from_str_accepts_prefixes_separators_and_any_case() {
+        for input in ["ws", "WS", "surrealdb+ws", " ws:// "] {
+            assert_eq!(input.parse(), Ok(ConnectionProtocol::Ws), "{input:?}");
+        }
+    }
+
+    #[cfg(feature = "ws")]
+    #[test]
+    fn from_str_rejects_server_urls() {
+        assert!(matches!(
+            "ws://127.0.0.1:8000".parse::<ConnectionProtocol>(),
+            Err(ConfigError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn from_str_rejects_unknown_schemes() {
+        assert_eq!(
+            "gopher".parse::<ConnectionProtocol>(),
+            Err(ConfigError::UnsupportedScheme("gopher".to_owned()))
+        );
+    }
+
+    #[cfg(not(feature = "kv-mem"))]
+    #[test]
+    fn from_str_names_protocols_that_are_not_compiled_in() {
+        assert_eq!(
+            "mem".parse::<ConnectionProtocol>(),
+            Err(ConfigError::ProtocolNotEnabled("mem".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use mobc::async_trait;
+use zeroize::Zeroize;
+
+use crate::credentials::Credentials;
+use crate::error::BoxError;
+use crate::secret::Secret;
+
+/// Supplies the credentials each new connection signs in with.
+///
+/// The manager asks its provider every time it opens a connection, so rotated
+/// credentials are picked up by new connections while existing ones age out
+/// through the pool's `max_lifetime`. Wrap slow providers in
+/// [`CachedCredentials`] to avoid a lookup per connection.
+#[async_trait]
+pub trait CredentialProvider: fmt::Debug + Send + Sync + 'static {
+    /// Returns the credentials to use for the next connection.
+    async fn credentials(&self) -> Result<Credentials, BoxError>;
+}
+
+#[async_trait]
+impl CredentialProvider for Credentials {
+    async fn credentials(&self) -> Result<Credentials, BoxError> {
+        Ok(self.clone())
+    }
+}
+
+#[async_trait]
+impl<P: CredentialProvider + ?Sized> CredentialProvider for Arc<P> {
+    async fn credentials(&self) -> Result<Credentials, BoxError> {
+        (**self).credentials().await
+    }
+}
+
+/// Caches the credentials of another provider for a fixed time.
+#[derive(Debug)]
+pub struct CachedCredentials<P> {
+    inner: P,
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, Credentials)>>,
+}
+
+impl<P: CredentialProvider> CachedCredentials<P> {
+    /// Caches credentials from `inner` for `ttl` after each lookup.
+    pub fn new(inner: P, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl<P: CredentialProvider> CredentialProvider for CachedCredentials<P> {
+    async fn credentials(&self) -> Result<Credentials, BoxError> {
+        if let Some((fetched_at, credentials)) = &*self.cached.lock().unwrap() {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(credentials.clone());
+            }
+        }
+
+        let credentials = self.inner.credentials().await?;
+        *self.cached.lock().unwrap() = Some((Instant::now(), credentials.clone()));
+        Ok(credentials)
+    }
+}
+
+/// Reads a secret (password or token) from a file, re-reading it whenever the
+/// file's modification time changes.
+///
+/// This fits secrets mounted by an orchestrator or written by a secrets
+/// manager agent, which are rotated by rewriting the file in place.
+pub struct FileCredentials {
+    path: PathBuf,
+    build: Box<dyn Fn(Secret) -> Credentials + Send + Sync>,
+    cached: Mutex<Option<(SystemTime, Credentials)>>,
+}
+
+impl FileCredentials {
+    /// Reads the file's contents (without trailing whitespace) as a secret and
+    /// turns it into credentials with `build`, e.g.
+    /// `|password| Credentials::root("svc", password)`.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        build: impl Fn(Secret) -> Credentials + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            build: Box::new(build),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Reads a pre-issued token from the file.
+    pub fn token(path: impl Into<PathBuf>) -> Self {
+        Self::new(path, Credentials::Token)
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for FileCredentials {
+    async fn credentials(&self) -> Result<Credentials, BoxError> {
+        let modified = tokio::fs::metadata(&self.path).await?.modified()?;
+        if let Some((read_at, credentials)) = &*self.cached.lock().unwrap() {
+            if *read_at == modified {
+                return Ok(credentials.clone());
+            }
+        }
+
+        let mut contents = tokio::fs::read_to_string(&self.path).await?;
+        let secret = Secret::new(contents.trim_end());
+        contents.zeroize();
+        if secret.expose().is_empty() {
+            return Err(format!("credential file `{}` is empty", self.path.display()).into());
+        }
+
+        let credentials = (self.build)(secret);
+        *self.cached.lock().unwrap() = Some((modified, credentials.clone()));
+        Ok(credentials)
+    }
+}
+
+impl fmt::Debug for FileCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileCredentials")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Hands out `token-1`, `token-2`, ... and counts its lookups.
+    #[derive(Debug, Default)]
+    struct Counter(AtomicUsize);
+
+    #[async_trait]
+    impl CredentialProvider for Counter {
+        async fn credentials(&self) -> Result<Credentials, BoxError> {
+            let n = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(Credentials::token(format!("token-{n}")))
+        }
+    }
+
+    fn token(credentials: Credentials) -> String {
+        match credentials {
+            Credentials::Token(token) => token.expose().to_owned(),
+            other => panic!("expected a token, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn cached_credentials_expire_after_the_ttl() {
+        let cached = CachedCredentials::new(Counter::default(), Duration::from_millis(50));
+        assert_eq!(token(cached.credentials().await.unwrap()), "token-1");
+        assert_eq!(token(cached.credentials().await.unwrap()), "token-1");
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(token(cached.credentials().await.unwrap()), "token-2");
+        assert_eq!(cached.inner.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn file_credentials_reread_the_file_when_it_changes() {
+        let path = std::env::temp_dir().join(format!(
+            "mobc-surrealdb-provider-test-{}",
+            std::process::id()
+        ));
+        let write = |contents: &str, modified: SystemTime| {
+            fs::write(&path, contents).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        let provider = FileCredentials::token(&path);
+        let then = SystemTime::now() - Duration::from_secs(60);
+
+        write("first\n", then);
+        assert_eq!(token(provider.credentials().await.unwrap()), "first");
+
+        // Same modification time: the cached credentials are kept.
+        write("second\n", then);
+        assert_eq!(token(provider.credentials().await.unwrap()), "first");
+
+        write("third\n", SystemTime::now());
+        assert_eq!(token(provider.credentials().await.unwrap()), "third");
+
+        write("\n", then);
+        assert!(provider.credentials().await.is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::error::ConfigError;
+
+/// How `connect()` retries dialing and signing in after a transient failure,
+/// such as a server that is restarting.
+///
+/// Retries back off exponentially from `initial_backoff`, capped at
+/// `max_backoff`. With jitter on (the default) each delay is drawn at random
+/// between zero and the computed backoff, so reconnecting clients spread out
+/// instead of retrying in lockstep. Only errors for which
+/// [`Error::is_retryable`](crate::Error::is_retryable) holds are retried;
+/// rejected credentials fail at once.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    max_elapsed: Option<Duration>,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Creates a policy making up to 5 attempts, backing off from 100ms up to
+    /// 5s, doubling each time, with jitter.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            max_elapsed: None,
+            jitter: true,
+        }
+    }
+
+    /// Sets how many attempts are made in total, the first one included.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the backoff before the first retry.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Caps the backoff between two attempts.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets the factor the backoff grows by after each retry. Defaults to 2.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Gives up once retrying would run past `max_elapsed` since the first
+    /// attempt. Unbounded by default; a `connect_timeout` still applies.
+    pub fn max_elapsed(mut self, max_elapsed: Option<Duration>) -> Self {
+        self.max_elapsed = max_elapsed;
+        self
+    }
+
+    /// Whether backoffs are randomised. Defaults to `true`.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_attempts == 0 {
+            return Err(invalid("`max_attempts` must be at least 1"));
+        }
+        if !self.multiplier.is_finite() || self.multiplier < 1.0 {
+            return Err(invalid(
+                "`multiplier` must be a finite number of at least 1",
+            ));
+        }
+        if self.initial_backoff > self.max_backoff {
+            return Err(invalid("`initial_backoff` must not exceed `max_backoff`"));
+        }
+        Ok(())
+    }
+
+    /// Returns how long to wait before the next attempt, after `attempt`
+    /// attempts failed over `elapsed`, or `None` if the policy gives up.
+    pub(crate) fn backoff(&self, attempt: u32, elapsed: Duration) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        // Grown one step at a time, so the backoff saturates at the cap
+        // instead of overflowing.
+        let mut backoff = self.initial_backoff;
+        for _ in 1..attempt {
+            backoff = Duration::try_from_secs_f64(backoff.as_secs_f64() * self.multiplier)
+                .map_or(self.max_backoff, |next| next.min(self.max_backoff));
+        }
+        let backoff = match self.jitter {
+            true => rand::thread_rng().gen_range(Duration::ZERO..=backoff),
+            false => backoff,
+        };
+        match self.max_elapsed {
+            Some(max_elapsed) if elapsed.saturating_add(backoff) > max_elapsed => None,
+            _ => Some(backoff),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(reason: &str) -> ConfigError {
+    ConfigError::InvalidRetry(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(300))
+            .jitter(false)
+    }
+
+    #[test]
+    fn backoff_grows_up_to_the_cap() {
+        let policy = policy().max_attempts(10);
+        let backoffs: Vec<_> = (1..5)
+            .map(|attempt| policy.backoff(attempt, Duration::ZERO))
+            .collect();
+        assert_eq!(
+            backoffs,
+            [100, 200, 300, 300].map(|ms| Some(Duration::from_millis(ms)))
+        );
+    }
+
+    #[test]
+    fn backoff_saturates_instead_of_overflowing() {
+        let policy = policy().max_attempts(u32::MAX).multiplier(f64::MAX);
+        assert_eq!(
+            policy.backoff(10_000, Duration::ZERO),
+            Some(Duration::from_millis(300))
+        );
+    }
+
+    #[test]
+    fn backoff_gives_up_after_max_attempts() {
+        let policy = policy().max_attempts(3);
+        assert!(policy.backoff(2, Duration::ZERO).is_some());
+        assert_eq!(policy.backoff(3, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn backoff_gives_up_past_max_elapsed() {
+        let policy = policy().max_elapsed(Some(Duration::from_secs(1)));
+        assert!(policy.backoff(1, Duration::from_millis(900)).is_some());
+        assert_eq!(policy.backoff(1, Duration::from_millis(901)), None);
+    }
+
+    #[test]
+    fn jitter_stays_within_the_backoff() {
+        let policy = policy().jitter(true).max_attempts(10);
+        for _ in 0..100 {
+            let backoff = policy.backoff(2, Duration::ZERO).unwrap();
+            assert!(backoff <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_inconsistent_policies() {
+        assert!(RetryPolicy::new().validate().is_ok());
+        for policy in [
+            RetryPolicy::new().max_attempts(0),
+            RetryPolicy::new().multiplier(0.5),
+            RetryPolicy::new().multiplier(f64::NAN),
+            RetryPolicy::new().initial_backoff(Duration::from_secs(10)),
+        ] {
+            assert!(matches!(
+                policy.validate(),
+                Err(ConfigError::InvalidRetry(_))
+            ));
+        }
+    }
+}
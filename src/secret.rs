@@ -0,0 +1,113 @@
+use std::fmt;
+
+use zeroize::Zeroize;
+
+/// A credential that never shows up in logs.
+///
+/// `Debug` and `Display` print `[REDACTED]`, and the memory holding the value
+/// is zeroed when the secret is dropped. The value itself is only read by the
+/// manager when it signs in.
+#[derive(Clone, Default)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wraps a credential.
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Returns the wrapped credential.
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Secret {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Credentials;
+
+    const PASSWORD: &str = "hunter2";
+
+    #[test]
+    fn secret_is_redacted() {
+        let secret = Secret::new(PASSWORD);
+        assert_eq!(format!("{secret}"), "[REDACTED]");
+        assert_eq!(format!("{secret:?}"), "Secret([REDACTED])");
+        assert_eq!(secret.expose(), PASSWORD);
+    }
+
+    #[test]
+    fn credentials_are_redacted() {
+        let root = format!("{:?}", Credentials::root("root", PASSWORD));
+        assert!(root.contains("root") && !root.contains(PASSWORD), "{root}");
+
+        let record = format!(
+            "{:#?}",
+            Credentials::record(
+                "app",
+                "app",
+                "user",
+                [("email", "info@example.com"), ("pass", PASSWORD)]
+            )
+        );
+        assert!(
+            record.contains("email") && record.contains("pass"),
+            "{record}"
+        );
+        assert!(
+            !record.contains("info@example.com") && !record.contains(PASSWORD),
+            "{record}"
+        );
+    }
+
+    #[cfg(feature = "ws")]
+    #[test]
+    fn manager_is_redacted() {
+        let manager = crate::SurrealDBConnectionManager::builder()
+            .address("127.0.0.1:8000")
+            .username("root")
+            .password(PASSWORD)
+            .build()
+            .unwrap();
+        let debug = format!("{manager:?}");
+        assert!(!debug.contains(PASSWORD), "{debug}");
+    }
+}
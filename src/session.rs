@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use surrealdb::engine::any::Any;
+use surrealdb::error::{Api, Db};
+use surrealdb::sql::Ident;
+use surrealdb::Surreal;
+
+/// Messages the server sends when a session lost or never had its identity.
+/// Remote engines only forward the message text, so this is what is matched.
+const AUTH_FAILURES: &[&str] = &[
+    "The session has expired",
+    "The token has expired",
+    "There was a problem with authentication",
+    "Auth was expected to be set but was unknown",
+    "IAM error",
+];
+
+/// Returns `true` if `err` means the session has to sign in again.
+pub(crate) fn is_auth_error(err: &surrealdb::Error) -> bool {
+    match err {
+        surrealdb::Error::Db(
+            Db::ExpiredSession
+            | Db::ExpiredToken
+            | Db::InvalidAuth
+            | Db::UnknownAuth
+            | Db::IamError(_),
+        ) => true,
+        surrealdb::Error::Db(_) => false,
+        surrealdb::Error::Api(Api::Query(message) | Api::Http(message) | Api::Ws(message)) => {
+            AUTH_FAILURES
+                .iter()
+                .any(|failure| message.contains(failure))
+        }
+        surrealdb::Error::Api(_) => false,
+    }
+}
+
+/// Returns `true` if the session's expiry (`$session.exp`) falls within
+/// `margin` from now. Sessions without an expiry never need refreshing.
+pub(crate) async fn expires_within(
+    conn: &Surreal<Any>,
+    margin: Duration,
+) -> Result<bool, surrealdb::Error> {
+    let mut response = conn.query("RETURN $session.exp").await?;
+    let expires_at: Option<i64> = response.take(0)?;
+    let Some(expires_at) = expires_at else {
+        return Ok(false);
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    Ok(expires_at.saturating_sub(now) <= margin.as_secs() as i64)
+}
+
+/// Returns `true` if the namespace is defined. Needs a root user.
+pub(crate) async fn namespace_exists(
+    conn: &Surreal<Any>,
+    namespace: &str,
+) -> Result<bool, surrealdb::Error> {
+    let mut response = conn.query("INFO FOR ROOT").await?;
+    let namespaces: Option<BTreeMap<String, String>> = response.take((0, "namespaces"))?;
+    Ok(namespaces.is_some_and(|namespaces| namespaces.contains_key(namespace)))
+}
+
+/// Returns `true` if the database is defined in the selected namespace.
+/// Needs a root or namespace user.
+pub(crate) async fn database_exists(
+    conn: &Surreal<Any>,
+    database: &str,
+) -> Result<bool, surrealdb::Error> {
+    let mut response = conn.query("INFO FOR NS").await?;
+    let databases: Option<BTreeMap<String, String>> = response.take((0, "databases"))?;
+    Ok(databases.is_some_and(|databases| databases.contains_key(database)))
+}
+
+/// Defines the namespace and, within the selected namespace, the database,
+/// unless they already exist.
+pub(crate) async fn define_selection(
+    conn: &Surreal<Any>,
+    namespace: &str,
+    database: Option<&str>,
+) -> Result<(), surrealdb::Error> {
+    let mut statements = format!("DEFINE NAMESPACE IF NOT EXISTS {};", Ident::from(namespace));
+    if let Some(database) = database {
+        statements += &format!(" DEFINE DATABASE IF NOT EXISTS {};", Ident::from(database));
+    }
+    conn.query(statements).await?.check()?;
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+#[cfg(feature = "rustls")]
+use std::sync::Arc;
+
+use rustls_pemfile::Item;
+use surrealdb::opt::Config;
+
+use crate::error::ConfigError;
+use crate::secret::Secret;
+
+/// The TLS library used for `wss` and `https` connections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// rustls with the ring crypto provider (the default when enabled).
+    #[cfg(feature = "rustls")]
+    #[default]
+    Rustls,
+    /// The platform's native TLS library (OpenSSL, Secure Transport, SChannel).
+    #[cfg(feature = "native-tls")]
+    #[cfg_attr(not(feature = "rustls"), default)]
+    NativeTls,
+}
+
+/// TLS settings for `wss` and `https` connections: which certificate
+/// authorities to trust, an optional client certificate for mutual TLS, and
+/// the TLS library to use.
+///
+/// Certificates and keys are PEM encoded. Without any settings the public
+/// web PKI roots are trusted, as they are when no `TlsConfig` is given.
+#[derive(Clone)]
+pub struct TlsConfig {
+    backend: TlsBackend,
+    root_certificates: Vec<Vec<u8>>,
+    public_roots: bool,
+    client_identity: Option<(Vec<u8>, Secret)>,
+}
+
+impl TlsConfig {
+    /// Creates a config trusting the public web PKI roots.
+    pub fn new() -> Self {
+        Self {
+            backend: TlsBackend::default(),
+            root_certificates: Vec::new(),
+            public_roots: true,
+            client_identity: None,
+        }
+    }
+
+    /// Selects the TLS library. Defaults to rustls when it is enabled.
+    pub fn backend(mut self, backend: TlsBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Trusts the certificate authorities in `pem`, e.g. a private CA or a
+    /// self-signed server certificate. May be called more than once.
+    pub fn root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Whether the public web PKI roots (rustls) or the platform's trust store
+    /// (native-tls) are trusted alongside any added root certificate.
+    /// Defaults to `true`; turn it off to trust a private CA only.
+    pub fn public_roots(mut self, enabled: bool) -> Self {
+        self.public_roots = enabled;
+        self
+    }
+
+    /// Presents a client certificate for mutual TLS. `certificate_chain`
+    /// holds the client certificate followed by any intermediates, and
+    /// `private_key` its PKCS#8 (or, with rustls, PKCS#1/SEC1) key.
+    pub fn client_identity(
+        mut self,
+        certificate_chain: impl Into<Vec<u8>>,
+        private_key: impl Into<Secret>,
+    ) -> Self {
+        self.client_identity = Some((certificate_chain.into(), private_key.into()));
+        self
+    }
+
+    /// Builds the SurrealDB connection config, parsing every certificate and key.
+    pub(crate) fn surreal_config(&self) -> Result<Config, ConfigError> {
+        match self.backend {
+            #[cfg(feature = "rustls")]
+            TlsBackend::Rustls => Ok(Config::new().rustls(self.rustls()?)),
+            #[cfg(feature = "native-tls")]
+            TlsBackend::NativeTls => Ok(Config::new().native_tls(self.native_tls()?)),
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    fn rustls(&self) -> Result<rustls::ClientConfig, ConfigError> {
+        let mut roots = rustls::RootCertStore::empty();
+        if self.public_roots {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        for pem in &self.root_certificates {
+            for certificate in certificates("root certificate", pem)? {
+                roots.add(certificate.into()).map_err(invalid)?;
+            }
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(invalid)?
+            .with_root_certificates(roots);
+        match &self.client_identity {
+            Some((chain, key)) => {
+                let chain = certificates("client certificate", chain)?
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+                let key = rustls_pemfile::private_key(&mut key.expose().as_bytes())
+                    .map_err(invalid)?
+                    .ok_or_else(|| invalid("no private key found in the client key PEM"))?;
+                builder.with_client_auth_cert(chain, key).map_err(invalid)
+            }
+            None => Ok(builder.with_no_client_auth()),
+        }
+    }
+
+    #[cfg(feature = "native-tls")]
+    fn native_tls(&self) -> Result<native_tls::TlsConnector, ConfigError> {
+        let mut builder = native_tls::TlsConnector::builder();
+        builder.disable_built_in_roots(!self.public_roots);
+        for pem in &self.root_certificates {
+            for certificate in certificates("root certificate", pem)? {
+                builder.add_root_certificate(
+                    native_tls::Certificate::from_der(&certificate).map_err(invalid)?,
+                );
+            }
+        }
+        if let Some((chain, key)) = &self.client_identity {
+            let identity = native_tls::Identity::from_pkcs8(chain, key.expose().as_bytes())
+                .map_err(invalid)?;
+            builder.identity(identity);
+        }
+        builder.build().map_err(invalid)
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("backend", &self.backend)
+            .field("root_certificates", &self.root_certificates.len())
+            .field("public_roots", &self.public_roots)
+            .field("client_identity", &self.client_identity.is_some())
+            .finish()
+    }
+}
+
+/// Reads every certificate out of a PEM bundle, which must hold at least one.
+fn certificates(what: &str, pem: &[u8]) -> Result<Vec<Vec<u8>>, ConfigError> {
+    let mut certificates = Vec::new();
+    for item in rustls_pemfile::read_all(&mut &pem[..]) {
+        if let Item::X509Certificate(certificate) = item.map_err(invalid)? {
+            certificates.push(certificate.to_vec());
+        }
+    }
+    if certificates.is_empty() {
+        return Err(invalid(format!("no certificate found in the {what} PEM")));
+    }
+    Ok(certificates)
+}
+
+fn invalid(reason: impl fmt::Display) -> ConfigError {
+    ConfigError::InvalidTls(reason.to_string())
+}